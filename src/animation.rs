@@ -0,0 +1,154 @@
+use crate::{
+    energy::Energy,
+    error::{Error, Result},
+    seam_carver::{resize_matrix, SeamOperation},
+    structs::{
+        color::CustomColor, interpolation::Interpolation, matrix::Matrix, window_size::WindowSize,
+    },
+    utils::matrix_to_rgba_image,
+};
+use ::image::{
+    codecs::gif::{GifEncoder, Repeat},
+    error::{EncodingError, ImageFormatHint},
+    imageops, Delay, Frame, ImageError, ImageFormat, Rgba, RgbaImage,
+};
+use std::{fs::File, io::BufWriter, path::Path};
+
+#[cfg(test)]
+#[path = "tests/animation.rs"]
+mod animation_tests;
+
+const SEAM_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// Collects the intermediate images of a resize so they can be encoded as an
+/// animation, optionally with the seam about to be removed or inserted drawn
+/// on top of every frame.
+pub struct CarvingRecorder {
+    pub draw_seams: bool,
+    frames: Vec<RgbaImage>,
+}
+
+impl CarvingRecorder {
+    pub fn new(draw_seams: bool) -> Self {
+        CarvingRecorder {
+            draw_seams,
+            frames: Vec::new(),
+        }
+    }
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn record(&mut self, matrix: &Matrix<CustomColor>, operation: Option<&SeamOperation>) {
+        let mut frame = matrix_to_rgba_image(matrix);
+        if let (true, Some(operation)) = (self.draw_seams, operation) {
+            match operation {
+                SeamOperation::CarveVertical(seam) | SeamOperation::InsertVertical(seam) => {
+                    seam.columns.iter().enumerate().for_each(|(row, column)| {
+                        frame.put_pixel(*column as u32, row as u32, SEAM_COLOR)
+                    })
+                }
+                SeamOperation::CarveHorizontal(seam) | SeamOperation::InsertHorizontal(seam) => {
+                    seam.rows.iter().enumerate().for_each(|(column, row)| {
+                        frame.put_pixel(column as u32, *row as u32, SEAM_COLOR)
+                    })
+                }
+            }
+        }
+        self.frames.push(frame);
+    }
+
+    /// Every recorded frame placed in the top left corner of a transparent
+    /// canvas large enough for the biggest of them, which is the original
+    /// image unless seams were inserted.
+    pub fn padded_frames(&self) -> Vec<RgbaImage> {
+        let width = self.frames.iter().map(|frame| frame.width()).max();
+        let height = self.frames.iter().map(|frame| frame.height()).max();
+        self.frames
+            .iter()
+            .map(|frame| {
+                let mut canvas = RgbaImage::new(width.unwrap(), height.unwrap());
+                imageops::replace(&mut canvas, frame, 0, 0);
+                canvas
+            })
+            .collect()
+    }
+
+    /// Writes the padded frames as a looping GIF. Fails if nothing was
+    /// recorded.
+    pub fn save_gif(&self, path: &Path, frame_delay_ms: u32) -> Result<()> {
+        if self.frames.is_empty() {
+            return Err(Error::DegenerateImage {
+                width: 0,
+                height: 0,
+            });
+        }
+        let mut encoder = GifEncoder::new(BufWriter::new(File::create(path)?));
+        encoder.set_repeat(Repeat::Infinite)?;
+        Ok(
+            encoder.encode_frames(self.padded_frames().into_iter().map(|frame| {
+                Frame::from_parts(frame, 0, 0, Delay::from_numer_denom_ms(frame_delay_ms, 1))
+            }))?,
+        )
+    }
+
+    /// Writes the padded frames as an APNG. Fails if nothing was recorded,
+    /// like `save_gif`.
+    pub fn save_apng(&self, path: &Path, frame_delay_ms: u16) -> Result<()> {
+        let frames = self.padded_frames();
+        let Some(first) = frames.first() else {
+            return Err(Error::DegenerateImage {
+                width: 0,
+                height: 0,
+            });
+        };
+        let apng_error = |error: png::EncodingError| {
+            ImageError::Encoding(EncodingError::new(
+                ImageFormatHint::Exact(ImageFormat::Png),
+                error,
+            ))
+        };
+
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            first.width(),
+            first.height(),
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames.len() as u32, 0)
+            .map_err(apng_error)?;
+        encoder
+            .set_frame_delay(frame_delay_ms, 1000)
+            .map_err(apng_error)?;
+        let mut writer = encoder.write_header().map_err(apng_error)?;
+        for frame in &frames {
+            writer
+                .write_image_data(frame.as_raw())
+                .map_err(apng_error)?;
+        }
+        Ok(writer.finish().map_err(apng_error)?)
+    }
+}
+
+/// Resizes `matrix` to `target` while recording every intermediate image,
+/// including the final one.
+pub fn record_resize(
+    matrix: &mut Matrix<CustomColor>,
+    energy: &Energy,
+    interpolation: Interpolation,
+    target: &WindowSize,
+    draw_seams: bool,
+) -> Result<CarvingRecorder> {
+    let mut recorder = CarvingRecorder::new(draw_seams);
+    resize_matrix(
+        matrix,
+        energy,
+        interpolation,
+        target,
+        |step_matrix, operation| recorder.record(step_matrix, Some(operation)),
+    )?;
+    recorder.record(matrix, None);
+    Ok(recorder)
+}
@@ -0,0 +1,240 @@
+use crate::{
+    energy::Energy,
+    error::Result,
+    seam_carver::{resize_matrix, SeamOperation},
+    structs::{interpolation::Interpolation, target_size::Target, window_size::WindowSize},
+    utils::*,
+};
+use ::image::ImageFormat;
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Instant,
+};
+
+#[cfg(test)]
+#[path = "tests/batch.rs"]
+mod batch_tests;
+
+/// Batch job: every image below `input` resized to every target on `energy`,
+/// with inserted pixels filled by `interpolation`, written to the same
+/// relative path below `output` with the size appended to the name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Batch {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub targets: Vec<Target>,
+    pub energy: Energy,
+    pub interpolation: Interpolation,
+    pub workers: usize,
+}
+
+/// Manifest line for one image resized to one target. Sizes are zero and
+/// `output` is empty when the image could not be read.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BatchRecord {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub source_width: usize,
+    pub source_height: usize,
+    pub width: usize,
+    pub height: usize,
+    pub carved_seams: usize,
+    pub inserted_seams: usize,
+    pub seconds: f64,
+    pub error: Option<String>,
+}
+
+/// Files below `directory` the `image` crate knows by their extension, in a
+/// stable order, leaving out everything below `excluded`.
+pub fn find_images(directory: &Path, excluded: &Path) -> Result<Vec<PathBuf>> {
+    // a missing directory cannot hold images yet
+    let excluded = fs::canonicalize(excluded).ok();
+    let mut images = Vec::new();
+    let mut directories = vec![directory.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if excluded.is_none() || fs::canonicalize(&path).ok() != excluded {
+                    directories.push(path);
+                }
+            } else if ImageFormat::from_path(&path).is_ok() {
+                images.push(path);
+            }
+        }
+    }
+    images.sort();
+    Ok(images)
+}
+
+impl Batch {
+    /// Output path of `image` resized to `size`, e.g. `photos/cat.png` at
+    /// 300x200 becomes `<output>/photos/cat_300x200.png`. `copy` counts the
+    /// targets of the image that resolved to `size` so far, including this
+    /// one, and is appended from the second on, as in `cat_300x200_2.png`.
+    pub fn output_path(&self, image: &Path, size: &WindowSize, copy: usize) -> PathBuf {
+        let relative = image.strip_prefix(&self.input).unwrap_or(image);
+        let stem = relative.file_stem().unwrap_or_default().to_string_lossy();
+        let mut name = format!("{}_{}x{}", stem, size.width, size.height);
+        if copy > 1 {
+            name = format!("{}_{}", name, copy);
+        }
+        if let Some(extension) = relative.extension() {
+            name = format!("{}.{}", name, extension.to_string_lossy());
+        }
+        self.output.join(relative.with_file_name(name))
+    }
+
+    /// Resizes `image` to every target, one record per target.
+    fn process(&self, image: &Path) -> Vec<BatchRecord> {
+        let failed = |error: String| BatchRecord {
+            input: image.to_path_buf(),
+            output: PathBuf::new(),
+            source_width: 0,
+            source_height: 0,
+            width: 0,
+            height: 0,
+            carved_seams: 0,
+            inserted_seams: 0,
+            seconds: 0.0,
+            error: Some(error),
+        };
+        let source = match load_matrix(image) {
+            Ok(source) => source,
+            Err(error) => {
+                return self
+                    .targets
+                    .iter()
+                    .map(|_| failed(error.to_string()))
+                    .collect()
+            }
+        };
+        let source_size = WindowSize {
+            height: source.height(),
+            width: source.width(),
+        };
+
+        let mut sizes = Vec::new();
+        self.targets
+            .iter()
+            .map(|target| {
+                let start = Instant::now();
+                // a fixed target ignores the window, so pass the image itself
+                let size = target.resolve(&source_size, &source_size);
+                sizes.push(size.clone());
+                let copy = sizes.iter().filter(|earlier| **earlier == size).count();
+                let output = self.output_path(image, &size, copy);
+                let mut matrix = source.clone();
+                let (mut carved_seams, mut inserted_seams) = (0, 0);
+                let resized = resize_matrix(
+                    &mut matrix,
+                    &self.energy,
+                    self.interpolation,
+                    &size,
+                    |_, operation| match operation {
+                        SeamOperation::CarveVertical(_) | SeamOperation::CarveHorizontal(_) => {
+                            carved_seams += 1
+                        }
+                        SeamOperation::InsertVertical(_) | SeamOperation::InsertHorizontal(_) => {
+                            inserted_seams += 1
+                        }
+                    },
+                );
+                let saved = resized
+                    .and_then(|_| Ok(output.parent().map_or(Ok(()), fs::create_dir_all)?))
+                    .and_then(|_| save_matrix(&matrix, &output));
+
+                BatchRecord {
+                    input: image.to_path_buf(),
+                    output,
+                    source_width: source_size.width,
+                    source_height: source_size.height,
+                    width: matrix.width(),
+                    height: matrix.height(),
+                    carved_seams,
+                    inserted_seams,
+                    seconds: start.elapsed().as_secs_f64(),
+                    error: saved.err().map(|error| error.to_string()),
+                }
+            })
+            .collect()
+    }
+
+    /// Processes all images on `workers` threads, skipping the output
+    /// directory if it is inside the input one. Records are in the order of
+    /// `find_images`, then of the targets, however the work was scheduled.
+    pub fn run(&self) -> Result<Vec<BatchRecord>> {
+        let images = find_images(&self.input, &self.output)?;
+        let next_image = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..self.workers.clamp(1, images.len().max(1)) {
+                let sender = sender.clone();
+                let (images, next_image) = (&images, &next_image);
+                scope.spawn(move || loop {
+                    let index = next_image.fetch_add(1, Ordering::Relaxed);
+                    let Some(image) = images.get(index) else {
+                        break;
+                    };
+                    // the receiver outlives the scope, so sending cannot fail
+                    sender.send((index, self.process(image))).unwrap();
+                });
+            }
+        });
+        drop(sender);
+
+        let mut results = receiver.into_iter().collect::<Vec<_>>();
+        results.sort_by_key(|(index, _)| *index);
+        Ok(results
+            .into_iter()
+            .flat_map(|(_, records)| records)
+            .collect())
+    }
+}
+
+fn csv_field(field: String) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// Writes `records` as CSV when `path` ends in `.csv` and as JSON otherwise.
+pub fn write_manifest(records: &[BatchRecord], path: &Path) -> Result<()> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    if !is_csv {
+        return Ok(fs::write(path, serde_json::to_string_pretty(records)?)?);
+    }
+
+    let mut csv = String::from(
+        "input,output,source_width,source_height,width,height,carved_seams,inserted_seams,seconds,error\n",
+    );
+    for record in records {
+        let fields = [
+            record.input.display().to_string(),
+            record.output.display().to_string(),
+            record.source_width.to_string(),
+            record.source_height.to_string(),
+            record.width.to_string(),
+            record.height.to_string(),
+            record.carved_seams.to_string(),
+            record.inserted_seams.to_string(),
+            format!("{:.3}", record.seconds),
+            record.error.clone().unwrap_or_default(),
+        ];
+        csv += &fields.map(csv_field).join(",");
+        csv.push('\n');
+    }
+    Ok(fs::write(path, csv)?)
+}
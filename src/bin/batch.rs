@@ -0,0 +1,50 @@
+use std::{env, fs};
+
+use seam_carver::{
+    batch::write_manifest,
+    cli::{parse_batch_args, BATCH_USAGE},
+    error::Error,
+};
+
+fn main() {
+    let options = parse_batch_args(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, BATCH_USAGE);
+        std::process::exit(2);
+    });
+
+    let records = options.batch.run().unwrap_or_else(|error| {
+        eprintln!("cannot read {}: {}", options.batch.input.display(), error);
+        std::process::exit(1);
+    });
+    let written = options
+        .manifest
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(Error::from)
+        .and_then(|_| write_manifest(&records, &options.manifest));
+    written.unwrap_or_else(|error| {
+        eprintln!("cannot write {}: {}", options.manifest.display(), error);
+        std::process::exit(1);
+    });
+
+    let failed = records
+        .iter()
+        .filter(|record| record.error.is_some())
+        .count();
+    for record in records.iter().filter(|record| record.error.is_some()) {
+        eprintln!(
+            "{}: {}",
+            record.input.display(),
+            record.error.as_deref().unwrap_or_default()
+        );
+    }
+    println!(
+        "{} outputs, {} failed, manifest in {}",
+        records.len(),
+        failed,
+        options.manifest.display()
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
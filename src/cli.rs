@@ -0,0 +1,324 @@
+use crate::{
+    batch::Batch,
+    energy::{DepthWeights, Energy, EnergyFunction},
+    structs::{
+        interpolation::Interpolation,
+        target_size::{Dimension, Target, TargetSize},
+    },
+    utils::BorderMode,
+};
+use std::{path::PathBuf, thread};
+
+#[cfg(test)]
+#[path = "tests/cli.rs"]
+mod cli_tests;
+
+pub const USAGE: &str =
+    "usage: seam_carver [IMAGE] [--depth DEPTH_MAP] [--energy gradient|depth|saliency|lab|lines] \
+[--depth-weight WEIGHT] [--nearness-weight WEIGHT] [--saliency-weight WEIGHT] [--line-weight WEIGHT] \
+[--border zero|replicate|reflect|wrap] \
+[--interpolation average|duplicate|linear|catmull-rom|gradient] \
+[--width PIXELS] [--height PIXELS] [--keep-width] [--keep-height] [--percent WIDTHxHEIGHT] \
+[--aspect WIDTH:HEIGHT] [--fit]";
+
+pub const BATCH_USAGE: &str = "usage: batch INPUT_DIRECTORY OUTPUT_DIRECTORY \
+[--size WIDTHxHEIGHT]... [--percent WIDTHxHEIGHT]... [--aspect WIDTH:HEIGHT]... \
+[--width PIXELS] [--height PIXELS] [--keep-width] [--keep-height] [--fit] \
+[--border zero|replicate|reflect|wrap] \
+[--interpolation average|duplicate|linear|catmull-rom|gradient] [--workers COUNT] [--manifest MANIFEST.json|MANIFEST.csv]";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CliOptions {
+    pub image: PathBuf,
+    pub depth: Option<PathBuf>,
+    pub energy: Energy,
+    pub interpolation: Interpolation,
+    pub target: Target,
+}
+
+fn parse_weight(flag: &str, value: Option<String>) -> Result<f32, String> {
+    value
+        .ok_or(format!("{} expects a value", flag))?
+        .parse::<f32>()
+        .map_err(|_| format!("{} expects a number", flag))
+}
+
+fn parse_size(flag: &str, value: Option<String>) -> Result<usize, String> {
+    match value.as_deref().map(str::parse::<usize>) {
+        Some(Ok(size)) if size > 0 => Ok(size),
+        _ => Err(format!("{} expects a positive whole number", flag)),
+    }
+}
+
+fn parse_border(value: Option<String>) -> Result<BorderMode, String> {
+    match value.as_deref() {
+        Some("zero") => Ok(BorderMode::Zero),
+        Some("replicate") => Ok(BorderMode::Replicate),
+        Some("reflect") => Ok(BorderMode::Reflect),
+        Some("wrap") => Ok(BorderMode::Wrap),
+        _ => Err("--border expects zero, replicate, reflect or wrap".to_string()),
+    }
+}
+
+fn parse_interpolation(value: Option<String>) -> Result<Interpolation, String> {
+    match value.as_deref() {
+        Some("average") => Ok(Interpolation::Average),
+        Some("duplicate") => Ok(Interpolation::Duplicate),
+        Some("linear") => Ok(Interpolation::Linear),
+        Some("catmull-rom") => Ok(Interpolation::CatmullRom),
+        Some("gradient") => Ok(Interpolation::GradientPreserving),
+        _ => Err(
+            "--interpolation expects average, duplicate, linear, catmull-rom or gradient"
+                .to_string(),
+        ),
+    }
+}
+
+/// Parses `first` and `second` separated by `separator`, or a single value
+/// used for both when `separator` is missing and `allow_single` is set.
+fn parse_pair<T: std::str::FromStr + Copy>(
+    flag: &str,
+    value: Option<String>,
+    separator: char,
+    allow_single: bool,
+) -> Result<(T, T), String> {
+    let value = value.ok_or(format!("{} expects a value", flag))?;
+    let parse = |text: &str| {
+        text.parse::<T>()
+            .map_err(|_| format!("{} expects numbers, not {}", flag, value))
+    };
+    match value.split_once(separator) {
+        Some((first, second)) => Ok((parse(first)?, parse(second)?)),
+        None if allow_single => parse(&value).map(|single| (single, single)),
+        None => Err(format!(
+            "{} expects two numbers separated by {}",
+            flag, separator
+        )),
+    }
+}
+
+/// Parses a `--percent` pair, which must be finite and positive.
+fn parse_percent(flag: &str, value: Option<String>) -> Result<(f32, f32), String> {
+    let (width, height) = parse_pair::<f32>(flag, value, 'x', true)?;
+    if !(width.is_finite() && width > 0.0 && height.is_finite() && height > 0.0) {
+        return Err(format!("{} expects positive percentages", flag));
+    }
+    Ok((width, height))
+}
+
+/// Target of `--width`/`--height` in pixels, with missing sides scaled in
+/// proportion or kept by `--keep-width`/`--keep-height`.
+fn pixel_size(
+    width: Option<usize>,
+    height: Option<usize>,
+    keep_width: bool,
+    keep_height: bool,
+) -> Result<TargetSize, String> {
+    let side = |pixels: Option<usize>, keep: bool| match (pixels, keep) {
+        (Some(_), true) => None,
+        (Some(pixels), false) => Some(Dimension::Pixels(pixels)),
+        (None, true) => Some(Dimension::Keep),
+        (None, false) => Some(Dimension::Proportional),
+    };
+    Ok(TargetSize::Size {
+        width: side(width, keep_width).ok_or("--width conflicts with --keep-width")?,
+        height: side(height, keep_height).ok_or("--height conflicts with --keep-height")?,
+    })
+}
+
+/// Parses the command line arguments following the program name. Passing a
+/// depth map switches to the depth-aware energy unless `--energy` says
+/// otherwise.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliOptions, String> {
+    let mut image = None;
+    let mut depth = None;
+    let mut energy = None;
+    let mut weights = DepthWeights::default();
+    let mut saliency_weight = 1.0;
+    let mut line_weight = 1.0;
+    let mut border = None;
+    let mut interpolation = Interpolation::default();
+    let mut width = None;
+    let mut height = None;
+    let mut keep_width = false;
+    let mut keep_height = false;
+    let mut percent = None;
+    let mut aspect = None;
+    let mut fit = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => depth = Some(PathBuf::from(args.next().ok_or("--depth expects a path")?)),
+            "--energy" => {
+                energy = Some(match args.next().as_deref() {
+                    Some(name @ ("gradient" | "depth" | "saliency" | "lab" | "lines")) => {
+                        name.to_string()
+                    }
+                    _ => {
+                        return Err(
+                            "--energy expects gradient, depth, saliency, lab or lines".to_string()
+                        )
+                    }
+                })
+            }
+            "--border" => border = Some(parse_border(args.next())?),
+            "--interpolation" => interpolation = parse_interpolation(args.next())?,
+            "--width" => width = Some(parse_size(&arg, args.next())?),
+            "--height" => height = Some(parse_size(&arg, args.next())?),
+            "--keep-width" => keep_width = true,
+            "--keep-height" => keep_height = true,
+            "--percent" => percent = Some(parse_percent(&arg, args.next())?),
+            "--aspect" => {
+                let (width, height) = parse_pair::<usize>(&arg, args.next(), ':', false)?;
+                if width == 0 || height == 0 {
+                    return Err("--aspect expects a ratio of positive numbers".to_string());
+                }
+                aspect = Some(TargetSize::Aspect { width, height });
+            }
+            "--fit" => fit = true,
+            "--depth-weight" => weights.discontinuity = parse_weight(&arg, args.next())?,
+            "--nearness-weight" => weights.nearness = parse_weight(&arg, args.next())?,
+            "--saliency-weight" => saliency_weight = parse_weight(&arg, args.next())?,
+            "--line-weight" => line_weight = parse_weight(&arg, args.next())?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ if image.is_none() => image = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    let function = match energy.as_deref() {
+        Some("depth") | None if depth.is_some() => EnergyFunction::DepthAware(weights),
+        Some("depth") => return Err("the depth energy needs a --depth map".to_string()),
+        Some("saliency") => EnergyFunction::Saliency {
+            weight: saliency_weight,
+        },
+        Some("lab") => EnergyFunction::LabDifference,
+        Some("lines") => EnergyFunction::LinePreserving {
+            weight: line_weight,
+        },
+        _ => EnergyFunction::GradientMagnitude,
+    };
+
+    let size = match (width.or(height), percent, aspect) {
+        (None, None, None) if !keep_width && !keep_height => TargetSize::Window,
+        (_, None, None) => pixel_size(width, height, keep_width, keep_height)?,
+        (None, Some((width, height)), None) if !keep_width && !keep_height => TargetSize::Size {
+            width: Dimension::Percent(width),
+            height: Dimension::Percent(height),
+        },
+        (None, None, Some(aspect)) if !keep_width && !keep_height => aspect,
+        _ => return Err("pixel sizes, --percent and --aspect cannot be combined".to_string()),
+    };
+
+    Ok(CliOptions {
+        image: image.unwrap_or(PathBuf::from("image.png")),
+        depth,
+        energy: Energy {
+            function,
+            border: border.unwrap_or(function.default_border()),
+        },
+        interpolation,
+        target: Target { size, fit },
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchOptions {
+    pub batch: Batch,
+    pub manifest: PathBuf,
+}
+
+/// Parses the arguments of the batch tool following the program name. Every
+/// `--size`, `--percent` and `--aspect` adds a target, `--width`, `--height`,
+/// `--keep-width` and `--keep-height` together add one more like in
+/// `parse_args`, and `--fit` applies to all of them. The manifest defaults
+/// to `manifest.json` in the output directory and there is a worker per
+/// available core unless `--workers` says otherwise.
+pub fn parse_batch_args(args: impl IntoIterator<Item = String>) -> Result<BatchOptions, String> {
+    let mut directories = Vec::new();
+    let mut sizes = Vec::new();
+    let mut width = None;
+    let mut height = None;
+    let mut keep_width = false;
+    let mut keep_height = false;
+    let mut fit = false;
+    let mut border = BorderMode::default();
+    let mut interpolation = Interpolation::default();
+    let mut workers = None;
+    let mut manifest = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => {
+                let (width, height) = parse_pair::<usize>(&arg, args.next(), 'x', false)?;
+                if width == 0 || height == 0 {
+                    return Err("--size expects positive sizes".to_string());
+                }
+                sizes.push(TargetSize::Size {
+                    width: Dimension::Pixels(width),
+                    height: Dimension::Pixels(height),
+                });
+            }
+            "--percent" => {
+                let (width, height) = parse_percent(&arg, args.next())?;
+                sizes.push(TargetSize::Size {
+                    width: Dimension::Percent(width),
+                    height: Dimension::Percent(height),
+                });
+            }
+            "--aspect" => {
+                let (width, height) = parse_pair::<usize>(&arg, args.next(), ':', false)?;
+                if width == 0 || height == 0 {
+                    return Err("--aspect expects a ratio of positive numbers".to_string());
+                }
+                sizes.push(TargetSize::Aspect { width, height });
+            }
+            "--width" => width = Some(parse_size(&arg, args.next())?),
+            "--height" => height = Some(parse_size(&arg, args.next())?),
+            "--keep-width" => keep_width = true,
+            "--keep-height" => keep_height = true,
+            "--fit" => fit = true,
+            "--border" => border = parse_border(args.next())?,
+            "--interpolation" => interpolation = parse_interpolation(args.next())?,
+            "--workers" => workers = Some(parse_size(&arg, args.next())?),
+            "--manifest" => {
+                manifest = Some(PathBuf::from(
+                    args.next().ok_or("--manifest expects a path")?,
+                ))
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ if directories.len() < 2 => directories.push(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    let [input, output]: [PathBuf; 2] = directories
+        .try_into()
+        .map_err(|_| "expected an input and an output directory".to_string())?;
+    if width.is_some() || height.is_some() || keep_width || keep_height {
+        sizes.push(pixel_size(width, height, keep_width, keep_height)?);
+    }
+    if sizes.is_empty() {
+        return Err(
+            "expected at least one --size, --percent, --aspect, --width or --height".to_string(),
+        );
+    }
+
+    Ok(BatchOptions {
+        manifest: manifest.unwrap_or(output.join("manifest.json")),
+        batch: Batch {
+            input,
+            output,
+            targets: sizes.into_iter().map(|size| Target { size, fit }).collect(),
+            energy: Energy {
+                function: EnergyFunction::GradientMagnitude,
+                border,
+            },
+            interpolation,
+            workers: workers
+                .unwrap_or(thread::available_parallelism().map_or(1, |workers| workers.get())),
+        },
+    })
+}
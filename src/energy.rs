@@ -0,0 +1,334 @@
+use crate::{
+    error::Result,
+    lines::{LineDetection, LineGuide},
+    structs::{color::CustomColor, layers::LayeredImage, matrix::Matrix},
+    utils::*,
+};
+
+#[cfg(test)]
+#[path = "tests/energy.rs"]
+mod energy_tests;
+
+/// Name of the `Layer<f32>` a `LayeredImage` keeps its depth map in. Larger
+/// depth values are farther away.
+pub const DEPTH_LAYER: &str = "depth";
+
+/// Name of the `LineGuide` layer the line-preserving energy keeps its lines
+/// in, see `Energy::prepare`.
+pub const LINES_LAYER: &str = "lines";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthWeights {
+    /// Weight of depth discontinuities, which mark object boundaries.
+    pub discontinuity: f32,
+    /// Weight of closeness to the camera, so seams prefer the background.
+    pub nearness: f32,
+}
+
+impl Default for DepthWeights {
+    fn default() -> Self {
+        DepthWeights {
+            discontinuity: 1.0,
+            nearness: 0.5,
+        }
+    }
+}
+
+/// Energy the seams are searched on.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EnergyFunction {
+    #[default]
+    GradientMagnitude,
+    /// Colour gradient plus depth terms, read from the `DEPTH_LAYER` of the
+    /// image. Images without one get the plain gradient magnitude.
+    DepthAware(DepthWeights),
+    /// Gradient magnitude plus `weight` times the frequency-tuned saliency,
+    /// which protects smooth but conspicuous regions.
+    Saliency { weight: f32 },
+    /// Colour difference ΔE between neighbours in CIELAB instead of the luma
+    /// difference, so isoluminant edges are kept.
+    LabDifference,
+    /// Gradient magnitude plus a penalty of `weight` on the dominant straight
+    /// lines, so seams cross them cleanly instead of bending them.
+    LinePreserving { weight: f32 },
+}
+
+impl EnergyFunction {
+    /// Border handling the function runs with unless told otherwise. Depth
+    /// maps replicate their border, since a zero border would make every
+    /// background pixel on the edge a depth discontinuity.
+    pub fn default_border(&self) -> BorderMode {
+        match self {
+            EnergyFunction::DepthAware(_) => BorderMode::Replicate,
+            _ => BorderMode::default(),
+        }
+    }
+}
+
+/// Energy function together with the border handling it runs with.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Energy {
+    pub function: EnergyFunction,
+    pub border: BorderMode,
+}
+
+impl Energy {
+    /// Sets up the state the energy keeps between seams. The line-preserving
+    /// energy detects its lines here, once, and adds them to `image` as the
+    /// `LINES_LAYER`, so they are carved along with the image and remember
+    /// where seams crossed them. Does nothing if the layer is already there.
+    pub fn prepare(&self, image: &mut LayeredImage) -> Result<()> {
+        if matches!(self.function, EnergyFunction::LinePreserving { .. })
+            && image.seam_layer::<LineGuide>(LINES_LAYER).is_none()
+        {
+            let gradient = gradient_magnitude_with_border(&image.color, self.border);
+            image.add_layer(
+                LINES_LAYER,
+                LineGuide::new(&gradient, &LineDetection::default()),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn energy_matrix(&self, image: &LayeredImage) -> Matrix<GradientMagnitudePoint> {
+        match &self.function {
+            EnergyFunction::DepthAware(weights) => {
+                if let Some(depth) = image.layer::<f32>(DEPTH_LAYER) {
+                    return depth_aware_energy(&image.color, &depth.matrix, weights, self.border);
+                }
+            }
+            EnergyFunction::LinePreserving { weight } => {
+                if let Some(guide) = image.seam_layer::<LineGuide>(LINES_LAYER) {
+                    return with_line_penalty(
+                        gradient_magnitude_with_border(&image.color, self.border),
+                        guide,
+                        *weight,
+                    );
+                }
+            }
+            _ => {}
+        }
+        self.color_energy(&image.color)
+    }
+
+    /// Energy of a colour image without layers, as the batch, video and
+    /// other pipelines carve it. The depth-aware energy has no depth map
+    /// there and gives the plain gradient magnitude.
+    pub fn color_energy(&self, color: &Matrix<CustomColor>) -> Matrix<GradientMagnitudePoint> {
+        let border = self.border;
+        match &self.function {
+            EnergyFunction::GradientMagnitude | EnergyFunction::DepthAware(_) => {
+                gradient_magnitude_with_border(color, border)
+            }
+            EnergyFunction::Saliency { weight } => saliency_energy(color, *weight, border),
+            EnergyFunction::LabDifference => lab_difference_energy(color, border),
+            EnergyFunction::LinePreserving { weight } => {
+                line_preserving_energy(color, *weight, &LineDetection::default(), border)
+            }
+        }
+    }
+}
+
+impl From<EnergyFunction> for Energy {
+    fn from(function: EnergyFunction) -> Self {
+        Energy {
+            function,
+            border: function.default_border(),
+        }
+    }
+}
+
+/// Gradient magnitude of `color` plus the weighted depth gradient and
+/// nearness, with depth normalized to the `0.0..=1.0` range first.
+pub fn depth_aware_energy(
+    color: &Matrix<CustomColor>,
+    depth: &Matrix<f32>,
+    weights: &DepthWeights,
+    border: BorderMode,
+) -> Matrix<GradientMagnitudePoint> {
+    let (min_depth, max_depth) = depth
+        .vector
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |acc, value| {
+            (acc.0.min(*value), acc.1.max(*value))
+        });
+    let range = if max_depth > min_depth {
+        max_depth - min_depth
+    } else {
+        1.0
+    };
+    let normalized_depth = Matrix::new(
+        depth
+            .vector
+            .iter()
+            .map(|value| (value - min_depth) / range)
+            .collect::<Vec<f32>>(),
+        depth.width(),
+    );
+
+    let mut result = gradient_magnitude_with_border(color, border);
+    result
+        .vector
+        .iter_mut()
+        .zip(gradient_values_with_border(&normalized_depth, border))
+        .zip(normalized_depth.vector.iter())
+        .for_each(|((point, discontinuity), depth)| {
+            point.value += weights.discontinuity * discontinuity + weights.nearness * (1.0 - depth);
+        });
+    result
+}
+
+/// Separable 5-tap binomial blur of one channel, replicating border pixels.
+fn blur(values: &[f32], width: usize) -> Vec<f32> {
+    const KERNEL: [f32; 5] = [1.0 / 16.0, 4.0 / 16.0, 6.0 / 16.0, 4.0 / 16.0, 1.0 / 16.0];
+    let height = values.len() / width;
+    let tap = |index: usize, offset: usize, length: usize| {
+        (index + offset).saturating_sub(2).min(length - 1)
+    };
+
+    let mut horizontal = vec![0.0; values.len()];
+    for row in 0..height {
+        for column in 0..width {
+            horizontal[row * width + column] = KERNEL
+                .iter()
+                .enumerate()
+                .map(|(offset, weight)| weight * values[row * width + tap(column, offset, width)])
+                .sum();
+        }
+    }
+
+    let mut result = vec![0.0; values.len()];
+    for row in 0..height {
+        for column in 0..width {
+            result[row * width + column] = KERNEL
+                .iter()
+                .enumerate()
+                .map(|(offset, weight)| {
+                    weight * horizontal[tap(row, offset, height) * width + column]
+                })
+                .sum();
+        }
+    }
+    result
+}
+
+/// Frequency-tuned saliency: distance of every slightly blurred pixel from
+/// the mean colour of the image, normalized to the `0.0..=1.0` range.
+pub fn frequency_tuned_saliency(color: &Matrix<CustomColor>) -> Matrix<f32> {
+    let width = color.width();
+    let channels = [
+        color
+            .vector
+            .iter()
+            .map(|pixel| pixel.r)
+            .collect::<Vec<f32>>(),
+        color
+            .vector
+            .iter()
+            .map(|pixel| pixel.g)
+            .collect::<Vec<f32>>(),
+        color
+            .vector
+            .iter()
+            .map(|pixel| pixel.b)
+            .collect::<Vec<f32>>(),
+    ];
+    let means = channels
+        .each_ref()
+        .map(|channel| channel.iter().sum::<f32>() / channel.len() as f32);
+    let blurred = channels.each_ref().map(|channel| blur(channel, width));
+
+    let saliency = (0..color.vector.len())
+        .map(|index| {
+            (0..3)
+                .map(|channel| (blurred[channel][index] - means[channel]).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        })
+        .collect::<Vec<f32>>();
+    let max = saliency.iter().cloned().fold(0.0, f32::max);
+    Matrix::new(
+        saliency
+            .into_iter()
+            .map(|value| if max > 0.0 { value / max } else { 0.0 })
+            .collect(),
+        width,
+    )
+}
+
+pub fn saliency_energy(
+    color: &Matrix<CustomColor>,
+    weight: f32,
+    border: BorderMode,
+) -> Matrix<GradientMagnitudePoint> {
+    let mut result = gradient_magnitude_with_border(color, border);
+    result
+        .vector
+        .iter_mut()
+        .zip(frequency_tuned_saliency(color).vector)
+        .for_each(|(point, saliency)| point.value += weight * saliency);
+    result
+}
+
+fn with_line_penalty(
+    mut energy: Matrix<GradientMagnitudePoint>,
+    guide: &LineGuide,
+    weight: f32,
+) -> Matrix<GradientMagnitudePoint> {
+    energy
+        .vector
+        .iter_mut()
+        .zip(guide.penalty(weight).vector)
+        .for_each(|(point, penalty)| point.value += penalty);
+    energy
+}
+
+/// Gradient magnitude plus the `LineGuide::penalty` of the lines detected on
+/// it. Every line pixel a seam passes through costs extra, so a seam crosses
+/// a line once where it has to rather than running along it. Lines detected
+/// afresh know nothing of earlier seams; `Energy` keeps them in a layer
+/// instead, so later seams cross each line where the first one did rather
+/// than bending building edges and horizons in several places.
+pub fn line_preserving_energy(
+    color: &Matrix<CustomColor>,
+    weight: f32,
+    detection: &LineDetection,
+    border: BorderMode,
+) -> Matrix<GradientMagnitudePoint> {
+    let gradient = gradient_magnitude_with_border(color, border);
+    let guide = LineGuide::new(&gradient, detection);
+    with_line_penalty(gradient, &guide, weight)
+}
+
+/// CIE76 ΔE of the central differences, divided by 100 to keep it in the
+/// range of the luma gradient. With `BorderMode::Zero` out-of-bounds
+/// neighbours are black.
+pub fn lab_difference_energy(
+    color: &Matrix<CustomColor>,
+    border: BorderMode,
+) -> Matrix<GradientMagnitudePoint> {
+    let lab = color
+        .vector
+        .iter()
+        .map(|pixel| pixel.to_lab())
+        .collect::<Vec<[f32; 3]>>();
+    Matrix::new(
+        central_differences(&lab, color.width(), border, [0.0; 3], |first, second| {
+            first
+                .iter()
+                .zip(second.iter())
+                .map(|(first, second)| (first - second).powi(2))
+                .sum::<f32>()
+                .sqrt()
+                / 100.0
+        })
+        .into_iter()
+        .zip(color.vector.iter())
+        .map(|(value, pixel)| GradientMagnitudePoint {
+            value,
+            is_inserted: pixel.is_inserted,
+        })
+        .collect(),
+        color.width(),
+    )
+}
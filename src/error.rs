@@ -0,0 +1,122 @@
+use ::image::ImageError;
+use std::{fmt, io};
+
+/// Everything that can go wrong in the crate's public operations.
+#[derive(Debug)]
+pub enum Error {
+    Image(ImageError),
+    Io(io::Error),
+    /// Seam sequence or manifest that cannot be read or written as JSON.
+    Json(serde_json::Error),
+    /// Image too small for the operation, e.g. an empty one or carving the
+    /// last column of one.
+    DegenerateImage {
+        width: usize,
+        height: usize,
+    },
+    /// Seam with a different number of pixels than the image it is applied
+    /// to has rows (vertical seams) or columns (horizontal seams).
+    SeamLength {
+        expected: usize,
+        found: usize,
+    },
+    /// Seam pixel `index` at `position`, outside an image `limit` pixels
+    /// across the seam.
+    SeamOutOfBounds {
+        index: usize,
+        position: usize,
+        limit: usize,
+    },
+    /// Seam moving `step` pixels between pixels `index - 1` and `index`, more
+    /// than it may.
+    DisconnectedSeam {
+        index: usize,
+        step: usize,
+    },
+    /// Images, layers or recorded sequences that must share a size but don't.
+    SizeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// Layer added under a name another layer of the image already has.
+    DuplicateLayer(String),
+    /// Background thread that could not be started or whose shared state was
+    /// poisoned by a panic.
+    Thread(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Image(error) => write!(formatter, "{}", error),
+            Error::Io(error) => write!(formatter, "{}", error),
+            Error::Json(error) => write!(formatter, "{}", error),
+            Error::DegenerateImage { width, height } => {
+                write!(
+                    formatter,
+                    "a {}x{} image is too small for this",
+                    width, height
+                )
+            }
+            Error::SeamLength { expected, found } => write!(
+                formatter,
+                "the seam has {} pixels but the image needs {}",
+                found, expected
+            ),
+            Error::SeamOutOfBounds {
+                index,
+                position,
+                limit,
+            } => write!(
+                formatter,
+                "seam pixel {} is at {}, outside the image of {} pixels",
+                index, position, limit
+            ),
+            Error::DisconnectedSeam { index, step } => write!(
+                formatter,
+                "the seam jumps {} pixels at pixel {}",
+                step, index
+            ),
+            Error::SizeMismatch { expected, found } => write!(
+                formatter,
+                "expected a {}x{} image, not {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            Error::DuplicateLayer(name) => {
+                write!(formatter, "the image already has a layer named {}", name)
+            }
+            Error::Thread(message) => write!(formatter, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Image(error) => Some(error),
+            Error::Io(error) => Some(error),
+            Error::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ImageError> for Error {
+    fn from(error: ImageError) -> Self {
+        Error::Image(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
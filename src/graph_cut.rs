@@ -0,0 +1,308 @@
+use crate::structs::matrix::{HorizontalSeam, Matrix, VerticalSeam};
+
+#[cfg(test)]
+#[path = "tests/graph_cut.rs"]
+mod graph_cut_tests;
+
+const INFINITE_CAPACITY: f64 = f64::INFINITY;
+
+/// Residual network solved with Dinic's algorithm. Every arc is stored next to
+/// its reverse, so `arc ^ 1` is always the partner of `arc`.
+struct FlowNetwork {
+    adjacency: Vec<Vec<usize>>,
+    targets: Vec<usize>,
+    capacities: Vec<f64>,
+}
+
+impl FlowNetwork {
+    fn new(nodes: usize) -> Self {
+        FlowNetwork {
+            adjacency: vec![Vec::new(); nodes],
+            targets: Vec::new(),
+            capacities: Vec::new(),
+        }
+    }
+
+    fn add_arc(&mut self, from: usize, to: usize, capacity: f64, reverse_capacity: f64) {
+        self.adjacency[from].push(self.targets.len());
+        self.targets.push(to);
+        self.capacities.push(capacity);
+        self.adjacency[to].push(self.targets.len());
+        self.targets.push(from);
+        self.capacities.push(reverse_capacity);
+    }
+
+    fn levels(&self, source: usize) -> Vec<usize> {
+        let mut levels = vec![usize::MAX; self.adjacency.len()];
+        let mut queue = std::collections::VecDeque::from([source]);
+        levels[source] = 0;
+        while let Some(node) = queue.pop_front() {
+            for &arc in &self.adjacency[node] {
+                let target = self.targets[arc];
+                if self.capacities[arc] > 0.0 && levels[target] == usize::MAX {
+                    levels[target] = levels[node] + 1;
+                    queue.push_back(target);
+                }
+            }
+        }
+        levels
+    }
+
+    fn max_flow(&mut self, source: usize, sink: usize) {
+        loop {
+            let mut levels = self.levels(source);
+            if levels[sink] == usize::MAX {
+                return;
+            }
+            let mut next_arc = vec![0; self.adjacency.len()];
+            // iterative depth first search for augmenting paths in the level graph
+            let mut path: Vec<usize> = Vec::new();
+            let mut node = source;
+            loop {
+                if node == sink {
+                    let flow = path
+                        .iter()
+                        .map(|&arc| self.capacities[arc])
+                        .fold(INFINITE_CAPACITY, f64::min);
+                    for &arc in &path {
+                        self.capacities[arc] -= flow;
+                        self.capacities[arc ^ 1] += flow;
+                    }
+                    path.clear();
+                    node = source;
+                    continue;
+                }
+
+                let mut advanced = false;
+                while next_arc[node] < self.adjacency[node].len() {
+                    let arc = self.adjacency[node][next_arc[node]];
+                    let target = self.targets[arc];
+                    if self.capacities[arc] > 0.0 && levels[target] == levels[node] + 1 {
+                        path.push(arc);
+                        node = target;
+                        advanced = true;
+                        break;
+                    }
+                    next_arc[node] += 1;
+                }
+
+                if !advanced {
+                    if node == source {
+                        break;
+                    }
+                    // dead end, never visit this node again in the current phase
+                    levels[node] = usize::MAX;
+                    let arc = path.pop().unwrap();
+                    node = self.targets[arc ^ 1];
+                    next_arc[node] += 1;
+                }
+            }
+        }
+    }
+
+    fn reachable(&self, source: usize) -> Vec<bool> {
+        self.levels(source)
+            .into_iter()
+            .map(|level| level != usize::MAX)
+            .collect()
+    }
+}
+
+/// Minimal connected vertical seams for a stack of equally sized cost
+/// matrices, found as a single minimum cut. With more than one matrix the
+/// seams also move by at most one column between consecutive matrices,
+/// which makes them a connected seam surface through a video volume.
+pub fn graph_cut_vertical_seams(costs: &[Matrix<f32>]) -> Vec<VerticalSeam> {
+    cut_vertical_seams(costs, None)
+}
+
+/// Like `graph_cut_vertical_seams`, but with forward energy: a cut pays for
+/// the luma differences between the pixels that become neighbours once the
+/// seam is removed, instead of for the energy of the removed pixels. Pixels
+/// with an infinite cost in `costs` are still avoided, all other costs are
+/// ignored. `luma` holds one matrix of the size of `costs` per frame.
+pub fn forward_graph_cut_vertical_seams(
+    costs: &[Matrix<f32>],
+    luma: &[Matrix<f32>],
+) -> Vec<VerticalSeam> {
+    cut_vertical_seams(costs, Some(luma))
+}
+
+/// Luma difference between the left and right neighbours of every pixel,
+/// the new horizontal edge removing the pixel creates. Border pixels have
+/// only one neighbour and create none.
+fn forward_removal_costs(luma: &Matrix<f32>) -> Vec<f32> {
+    let width = luma.width();
+    (0..luma.vector.len())
+        .map(|index| {
+            let column = index % width;
+            if column == 0 || column == width - 1 {
+                0.0
+            } else {
+                (luma.vector[index + 1] - luma.vector[index - 1]).abs()
+            }
+        })
+        .collect()
+}
+
+fn cut_vertical_seams(costs: &[Matrix<f32>], luma: Option<&[Matrix<f32>]>) -> Vec<VerticalSeam> {
+    let Some(first) = costs.first() else {
+        return Vec::new();
+    };
+    let width = first.width();
+    let height = first.height();
+    let depth = costs.len();
+    let pixels = width * height * depth;
+    let source = pixels;
+    let sink = pixels + 1;
+
+    // with forward energy the removal of a pixel costs the edge it creates,
+    // unless the pixel is avoided altogether
+    let removal_costs = costs
+        .iter()
+        .enumerate()
+        .map(|(frame, matrix)| match luma {
+            Some(luma) => forward_removal_costs(&luma[frame])
+                .into_iter()
+                .zip(matrix.vector.iter())
+                .map(|(forward, cost)| if cost.is_finite() { forward } else { *cost })
+                .collect(),
+            None => matrix.vector.clone(),
+        })
+        .collect::<Vec<Vec<f32>>>();
+    // a step of the seam between rows makes two more pixels neighbours, so
+    // the arcs between rows carry the luma difference of that pair: the arc
+    // down is cut when the seam steps left, the arc up when it steps right
+    let step_costs = |frame: usize, row: usize, column: usize| match luma {
+        Some(luma) => {
+            let at = |row: usize, column: usize| luma[frame].vector[row * width + column];
+            (
+                (at(row - 1, column - 1) - at(row, column)).abs(),
+                (at(row - 1, column) - at(row, column - 1)).abs(),
+            )
+        }
+        None => (0.0, 0.0),
+    };
+
+    // avoided pixels cost infinity, which a finite cut must still be able to pay
+    let finite_total = removal_costs
+        .iter()
+        .flatten()
+        .filter(|cost| cost.is_finite())
+        .map(|cost| *cost as f64)
+        .sum::<f64>()
+        + (0..depth)
+            .flat_map(|frame| {
+                (1..height).flat_map(move |row| (1..width).map(move |column| (frame, row, column)))
+            })
+            .map(|(frame, row, column)| {
+                let (down, up) = step_costs(frame, row, column);
+                (down + up) as f64
+            })
+            .sum::<f64>();
+    let capacity = |cost: f32| {
+        if cost.is_finite() {
+            cost as f64
+        } else {
+            finite_total + 1.0
+        }
+    };
+
+    let node = |frame: usize, row: usize, column: usize| (frame * height + row) * width + column;
+    let mut network = FlowNetwork::new(pixels + 2);
+    for (frame, frame_costs) in removal_costs.iter().enumerate() {
+        for row in 0..height {
+            network.add_arc(source, node(frame, row, 0), INFINITE_CAPACITY, 0.0);
+            for column in 0..width {
+                let cost = capacity(frame_costs[row * width + column]);
+                let current = node(frame, row, column);
+                if luma.is_some() && row > 0 && column > 0 {
+                    let (down, up) = step_costs(frame, row, column);
+                    network.add_arc(
+                        node(frame, row - 1, column),
+                        current,
+                        down as f64,
+                        up as f64,
+                    );
+                }
+                // cutting right of a pixel removes it, the infinite reverse arc
+                // makes sure every row is cut exactly once
+                if column < width - 1 {
+                    network.add_arc(
+                        current,
+                        node(frame, row, column + 1),
+                        cost,
+                        INFINITE_CAPACITY,
+                    );
+                } else {
+                    network.add_arc(current, sink, cost, 0.0);
+                }
+
+                // keep neighbouring rows and frames within one column
+                if column > 0 {
+                    if row > 0 {
+                        network.add_arc(
+                            current,
+                            node(frame, row - 1, column - 1),
+                            INFINITE_CAPACITY,
+                            0.0,
+                        );
+                    }
+                    if row < height - 1 {
+                        network.add_arc(
+                            current,
+                            node(frame, row + 1, column - 1),
+                            INFINITE_CAPACITY,
+                            0.0,
+                        );
+                    }
+                    if frame > 0 {
+                        network.add_arc(
+                            current,
+                            node(frame - 1, row, column - 1),
+                            INFINITE_CAPACITY,
+                            0.0,
+                        );
+                    }
+                    if frame < depth - 1 {
+                        network.add_arc(
+                            current,
+                            node(frame + 1, row, column - 1),
+                            INFINITE_CAPACITY,
+                            0.0,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    network.max_flow(source, sink);
+    let source_side = network.reachable(source);
+
+    (0..depth)
+        .map(|frame| VerticalSeam {
+            columns: (0..height)
+                .map(|row| {
+                    (0..width)
+                        .take_while(|column| source_side[node(frame, row, *column)])
+                        .count()
+                        - 1
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+impl Matrix<f32> {
+    pub fn graph_cut_vertical_seam(&self) -> VerticalSeam {
+        graph_cut_vertical_seams(std::slice::from_ref(self))
+            .pop()
+            .unwrap()
+    }
+    pub fn graph_cut_horizontal_seam(&self) -> HorizontalSeam {
+        HorizontalSeam {
+            rows: self.transpose().graph_cut_vertical_seam().columns,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use macroquad::prelude::*;
+
+pub mod animation;
+pub mod batch;
+pub mod cli;
+pub mod energy;
+pub mod error;
+pub mod graph_cut;
+pub mod lines;
+pub mod multi_operator;
+pub mod poisson;
+pub mod seam_carver;
+pub mod seam_finder;
+pub mod sequence;
+pub mod stereo;
+pub mod streaming;
+pub mod structs;
+pub mod symmetry;
+pub mod utils;
+pub mod video;
@@ -0,0 +1,304 @@
+use crate::{
+    error::Result,
+    structs::{
+        layers::SeamLayer,
+        matrix::{HorizontalSeam, Matrix, SeamConstraint, VerticalSeam},
+    },
+    utils::GradientMagnitudePoint,
+};
+use std::{any::Any, f32::consts::PI};
+
+#[cfg(test)]
+#[path = "tests/lines.rs"]
+mod lines_tests;
+
+const ANGLE_STEPS: usize = 180;
+/// Half the size, in angle steps and pixels, of the neighbourhood cleared
+/// around every accepted peak so one line is not detected twice.
+const SUPPRESSION_RADIUS: isize = 5;
+
+/// Straight line `x * cos(theta) + y * sin(theta) = rho`, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line {
+    pub rho: f32,
+    pub theta: f32,
+    /// Number of edge pixels lying on the line.
+    pub votes: usize,
+}
+
+impl Line {
+    pub fn distance(&self, x: usize, y: usize) -> f32 {
+        (x as f32 * self.theta.cos() + y as f32 * self.theta.sin() - self.rho).abs()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineDetection {
+    /// Pixels with at least this fraction of the maximum energy are edges.
+    pub edge_threshold: f32,
+    /// Edge pixels a line needs to be kept.
+    pub min_votes: usize,
+    pub max_lines: usize,
+}
+
+impl Default for LineDetection {
+    fn default() -> Self {
+        LineDetection {
+            edge_threshold: 0.5,
+            min_votes: 20,
+            max_lines: 8,
+        }
+    }
+}
+
+fn edge_mask(energy: &Matrix<GradientMagnitudePoint>, edge_threshold: f32) -> Vec<bool> {
+    let max = energy
+        .vector
+        .iter()
+        .map(|point| point.value)
+        .fold(0.0, f32::max);
+    energy
+        .vector
+        .iter()
+        .map(|point| max > 0.0 && point.value >= edge_threshold * max)
+        .collect()
+}
+
+/// Dominant straight lines of the energy map, strongest first, found with a
+/// Hough transform over its edge pixels.
+pub fn detect_lines(
+    energy: &Matrix<GradientMagnitudePoint>,
+    detection: &LineDetection,
+) -> Vec<Line> {
+    let width = energy.width();
+    let height = energy.height();
+    let max_rho = ((width * width + height * height) as f32).sqrt().ceil() as usize;
+    let rho_steps = 2 * max_rho + 1;
+    let angles = (0..ANGLE_STEPS)
+        .map(|step| {
+            let theta = step as f32 * PI / ANGLE_STEPS as f32;
+            (theta.cos(), theta.sin())
+        })
+        .collect::<Vec<(f32, f32)>>();
+
+    let mut accumulator = vec![0usize; ANGLE_STEPS * rho_steps];
+    for (index, _) in edge_mask(energy, detection.edge_threshold)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, is_edge)| *is_edge)
+    {
+        let (x, y) = ((index % width) as f32, (index / width) as f32);
+        for (step, (cos, sin)) in angles.iter().enumerate() {
+            let rho = (x * cos + y * sin).round() as isize + max_rho as isize;
+            accumulator[step * rho_steps + rho as usize] += 1;
+        }
+    }
+
+    let mut lines = Vec::with_capacity(detection.max_lines);
+    while lines.len() < detection.max_lines {
+        let Some((peak, votes)) = accumulator
+            .iter()
+            .cloned()
+            .enumerate()
+            .max_by_key(|(_, votes)| *votes)
+        else {
+            break;
+        };
+        if votes < detection.min_votes.max(1) {
+            break;
+        }
+        let (step, rho) = ((peak / rho_steps) as isize, (peak % rho_steps) as isize);
+        lines.push(Line {
+            rho: (rho - max_rho as isize) as f32,
+            theta: step as f32 * PI / ANGLE_STEPS as f32,
+            votes,
+        });
+
+        for angle_offset in -SUPPRESSION_RADIUS..=SUPPRESSION_RADIUS {
+            // theta wraps around to PI with the sign of rho flipped
+            let (angle, sign) = match step + angle_offset {
+                angle if angle < 0 => (angle + ANGLE_STEPS as isize, -1),
+                angle if angle >= ANGLE_STEPS as isize => (angle - ANGLE_STEPS as isize, -1),
+                angle => (angle, 1),
+            };
+            for rho_offset in -SUPPRESSION_RADIUS..=SUPPRESSION_RADIUS {
+                let rho = sign * (rho - max_rho as isize + rho_offset) + max_rho as isize;
+                if (0..rho_steps as isize).contains(&rho) {
+                    accumulator[angle as usize * rho_steps + rho as usize] = 0;
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Where a pixel stands relative to the detected lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinePixel {
+    /// Index of the strongest detected line through the pixel, if any.
+    pub line: Option<usize>,
+    /// Whether an earlier seam crossed the line right next to this pixel.
+    pub crossed: bool,
+}
+
+/// Lines detected once on the original image, kept as a mask that is carved
+/// and inserted into along with the image. Every seam marks the line pixels
+/// it crosses, so later seams can be steered to cross each line at the same
+/// position instead of bending it in several places. Stored in a
+/// `LayeredImage` it follows every seam operation by itself.
+#[derive(Clone)]
+pub struct LineGuide {
+    lines: Vec<Line>,
+    mask: Matrix<LinePixel>,
+}
+
+impl LineGuide {
+    /// Detects the lines of `energy`. Only its edge pixels count as on a
+    /// line, so the mask covers the visible segments rather than the whole
+    /// infinite lines.
+    pub fn new(energy: &Matrix<GradientMagnitudePoint>, detection: &LineDetection) -> Self {
+        let lines = detect_lines(energy, detection);
+        let width = energy.width();
+        let mask = edge_mask(energy, detection.edge_threshold)
+            .into_iter()
+            .enumerate()
+            .map(|(index, is_edge)| {
+                let (x, y) = (index % width, index / width);
+                LinePixel {
+                    line: lines
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, line)| is_edge && line.distance(x, y) <= 0.5)
+                        .max_by_key(|(_, line)| line.votes)
+                        .map(|(line, _)| line),
+                    crossed: false,
+                }
+            })
+            .collect();
+        LineGuide {
+            lines,
+            mask: Matrix::new(mask, width),
+        }
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+    pub fn mask(&self) -> &Matrix<LinePixel> {
+        &self.mask
+    }
+
+    /// Penalty of every pixel for a seam passing through it: `weight` times
+    /// the strength of its line relative to the strongest line, waived on
+    /// the pixels where the line was already crossed. Lines that were never
+    /// crossed cost the same everywhere.
+    pub fn penalty(&self, weight: f32) -> Matrix<f32> {
+        let max_votes = self.lines.iter().map(|line| line.votes).max().unwrap_or(1) as f32;
+        Matrix::new(
+            self.mask
+                .vector
+                .iter()
+                .map(|pixel| match pixel.line {
+                    Some(line) if !pixel.crossed => {
+                        weight * self.lines[line].votes as f32 / max_votes
+                    }
+                    _ => 0.0,
+                })
+                .collect(),
+            self.mask.width(),
+        )
+    }
+
+    /// Marks the `neighbours` of the pixel at `index` as crossed, if they lie
+    /// on the same line as it.
+    fn mark_crossing(&mut self, index: usize, neighbours: [Option<usize>; 2]) {
+        let Some(line) = self.mask.vector[index].line else {
+            return;
+        };
+        for neighbour in neighbours.into_iter().flatten() {
+            let pixel = &mut self.mask.vector[neighbour];
+            if pixel.line == Some(line) {
+                pixel.crossed = true;
+            }
+        }
+    }
+}
+
+/// Fill of inserted pixels: a copy of the seam pixel, crossed if it is on a
+/// line.
+fn inserted_line_pixel(neighbours: &[LinePixel], seam_index: usize) -> LinePixel {
+    let pixel = neighbours[seam_index];
+    LinePixel {
+        crossed: pixel.crossed || pixel.line.is_some(),
+        ..pixel
+    }
+}
+
+impl SeamLayer for LineGuide {
+    fn width(&self) -> usize {
+        self.mask.width()
+    }
+    fn height(&self) -> usize {
+        self.mask.height()
+    }
+    fn carve_vertical_seam(
+        &mut self,
+        seam: &VerticalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        seam.validate_with_constraint(&self.mask, constraint)?;
+        let width = self.mask.width();
+        for (row, column) in seam.columns.iter().enumerate() {
+            // the pixels left and right of the removed one become the crossing
+            let index = row * width + column;
+            self.mark_crossing(
+                index,
+                [
+                    column.checked_sub(1).map(|_| index - 1),
+                    (column + 1 < width).then_some(index + 1),
+                ],
+            );
+        }
+        self.mask
+            .carve_vertical_seam_with_constraint(seam, constraint)
+    }
+    fn carve_horizontal_seam(
+        &mut self,
+        seam: &HorizontalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        seam.validate_with_constraint(&self.mask, constraint)?;
+        let (width, height) = (self.mask.width(), self.mask.height());
+        for (column, row) in seam.rows.iter().enumerate() {
+            let index = row * width + column;
+            self.mark_crossing(
+                index,
+                [
+                    row.checked_sub(1).map(|_| index - width),
+                    (row + 1 < height).then_some(index + width),
+                ],
+            );
+        }
+        self.mask
+            .carve_horizontal_seam_with_constraint(seam, constraint)
+    }
+    fn insert_vertical_seam(
+        &mut self,
+        seam: &VerticalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        self.mask
+            .insert_vertical_seam_with_window(seam, 1, constraint, inserted_line_pixel)
+    }
+    fn insert_horizontal_seam(
+        &mut self,
+        seam: &HorizontalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        self.mask
+            .insert_horizontal_seam_with_window(seam, 1, constraint, inserted_line_pixel)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
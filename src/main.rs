@@ -2,12 +2,20 @@ use macroquad::prelude::*;
 use std::{
     env,
     sync::{Arc, RwLock},
+    thread::JoinHandle,
 };
 
-use crate::{seam_carver::spawn_seam_carver, structs::window_size::WindowSize};
-mod seam_carver;
-mod structs;
-mod utils;
+use seam_carver::{
+    cli::{parse_args, CliOptions, USAGE},
+    energy::DEPTH_LAYER,
+    error::Result,
+    seam_carver::spawn_seam_carver,
+    structs::{
+        layers::{Layer, LayeredImage},
+        window_size::WindowSize,
+    },
+    utils::{load_luma_matrix, load_matrix, matrix_to_image},
+};
 
 fn window_conf() -> Conf {
     Conf {
@@ -18,20 +26,69 @@ fn window_conf() -> Conf {
     }
 }
 
+type DisplayedImage = Arc<RwLock<Image>>;
+
+/// Loads the image and depth map and starts the seam carver thread, handing
+/// back the image to display and the thread.
+fn start(
+    options: CliOptions,
+    window_size: &Arc<RwLock<WindowSize>>,
+) -> Result<(DisplayedImage, JoinHandle<Result<()>>)> {
+    let mut image = LayeredImage::new(load_matrix(&options.image)?);
+    image.interpolation = options.interpolation;
+    if let Some(depth_path) = &options.depth {
+        let depth = load_luma_matrix(depth_path)?;
+        image.add_layer(DEPTH_LAYER, Layer::averaged(depth))?;
+    }
+
+    let displayed_image = Arc::new(RwLock::new(matrix_to_image(&image.color)));
+    let carver_thread = spawn_seam_carver(
+        &displayed_image,
+        window_size,
+        image,
+        options.energy,
+        options.target,
+    )?;
+    Ok((displayed_image, carver_thread))
+}
+
 #[macroquad::main(window_conf)]
 async fn main() {
     env::set_var("RUST_BACKTRACE", "1");
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        std::process::exit(2);
+    });
     let window_size = Arc::new(RwLock::new(WindowSize {
         height: screen_height() as usize,
         width: screen_width() as usize,
     }));
 
-    let displayed_image = Arc::new(RwLock::new(load_image("image.png").await.unwrap()));
-
-    spawn_seam_carver(&displayed_image, &window_size);
+    let image_path = options.image.clone();
+    let (displayed_image, carver_thread) = start(options, &window_size).unwrap_or_else(|error| {
+        eprintln!("{}: {}", image_path.display(), error);
+        std::process::exit(1);
+    });
+    let mut carver_thread = Some(carver_thread);
 
-    let mut displayed_image_clone = displayed_image.read().unwrap().clone();
+    let mut displayed_image_clone = displayed_image.read().map_or_else(
+        |poisoned| poisoned.into_inner().clone(),
+        |image| image.clone(),
+    );
     loop {
+        // the seam carver only stops when it fails
+        if carver_thread
+            .as_ref()
+            .is_some_and(|thread| thread.is_finished())
+        {
+            let error = match carver_thread.take().map(JoinHandle::join) {
+                Some(Ok(Err(error))) => error.to_string(),
+                _ => "the seam carver stopped unexpectedly".to_string(),
+            };
+            eprintln!("{}: {}", image_path.display(), error);
+        }
+
+        #[allow(clippy::single_match)]
         match window_size.try_read() {
             Ok(window_size_read_guard) => {
                 let next_screen_height = screen_height() as usize;
@@ -52,6 +109,7 @@ async fn main() {
             Err(_) => {}
         }
 
+        #[allow(clippy::single_match)]
         match displayed_image.try_read() {
             Ok(displayed_image_read_lock) => {
                 displayed_image_clone = displayed_image_read_lock.clone();
@@ -0,0 +1,227 @@
+use crate::{
+    energy::Energy,
+    error::{Error, Result},
+    seam_carver::resize_matrix,
+    structs::{
+        color::CustomColor, interpolation::Interpolation, matrix::Matrix, pixel::Pixel,
+        window_size::WindowSize,
+    },
+    utils::*,
+};
+use ::rand::thread_rng;
+
+#[cfg(test)]
+#[path = "tests/multi_operator.rs"]
+mod multi_operator_tests;
+
+/// How many columns each operator removed in a multi-operator reduction.
+/// Cropping runs first, then seam carving, then uniform scaling.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OperatorMix {
+    pub cropped: usize,
+    pub carved: usize,
+    pub scaled: usize,
+    pub distortion: f32,
+}
+
+/// Start of the `width` wide window of columns with the most energy in it,
+/// the content a crop should keep, together with that energy.
+fn best_crop_window(energy: &Matrix<GradientMagnitudePoint>, width: usize) -> (usize, f32) {
+    let column_energy = (0..energy.width())
+        .map(|column| {
+            energy
+                .vector
+                .iter()
+                .skip(column)
+                .step_by(energy.width())
+                .map(|point| point.value)
+                .sum::<f32>()
+        })
+        .collect::<Vec<f32>>();
+    (0..=energy.width() - width)
+        .map(|left| (left, column_energy[left..left + width].iter().sum::<f32>()))
+        .max_by(|first, second| first.1.total_cmp(&second.1))
+        .unwrap()
+}
+
+fn crop_columns(matrix: &Matrix<CustomColor>, left: usize, width: usize) -> Matrix<CustomColor> {
+    Matrix::new(
+        matrix
+            .vector
+            .chunks_exact(matrix.width())
+            .flat_map(|row| row[left..left + width].iter().cloned())
+            .collect(),
+        width,
+    )
+}
+
+/// Every row of `matrix` resampled to `width` with a triangle filter that
+/// widens with the reduction, so narrowing averages over all the columns it
+/// merges. Output pixels are inserted when their strongest source pixel is.
+fn scale_rows<T: Pixel>(matrix: &Matrix<T>, width: usize) -> Matrix<T> {
+    let source_width = matrix.width();
+    let ratio = source_width as f32 / width as f32;
+    let support = ratio.max(1.0);
+    let taps = (0..width)
+        .map(|column| {
+            let center = (column as f32 + 0.5) * ratio - 0.5;
+            let first = (center - support).ceil().max(0.0) as usize;
+            let last = ((center + support).floor() as usize).min(source_width - 1);
+            let weights = (first..=last)
+                .map(|source| (1.0 - (source as f32 - center).abs() / support).max(0.0))
+                .collect::<Vec<f32>>();
+            let total = weights.iter().sum::<f32>();
+            let strongest = weights
+                .iter()
+                .enumerate()
+                .max_by(|first, second| first.1.total_cmp(second.1))
+                .map_or(0, |(offset, _)| offset);
+            (
+                first,
+                weights.iter().map(|weight| weight / total).collect(),
+                strongest,
+            )
+        })
+        .collect::<Vec<(usize, Vec<f32>, usize)>>();
+
+    Matrix::new(
+        matrix
+            .vector
+            .chunks_exact(source_width)
+            .flat_map(|row| {
+                taps.iter().map(|(first, weights, strongest)| {
+                    let pixels = &row[*first..*first + weights.len()];
+                    let mut pixel = T::blend(pixels, weights);
+                    if pixels[*strongest].is_inserted() {
+                        pixel.mark_inserted();
+                    }
+                    pixel
+                })
+            })
+            .collect(),
+        width,
+    )
+}
+
+/// Uniform scaling of `matrix` to `width` by `height`, computed on the
+/// pixels themselves so no precision or inserted flags are lost.
+pub fn scale_matrix<T: Pixel>(matrix: &Matrix<T>, width: usize, height: usize) -> Matrix<T> {
+    let mut result = if width == matrix.width() {
+        matrix.clone()
+    } else {
+        scale_rows(matrix, width)
+    };
+    if height != result.height() {
+        result = scale_rows(&result.transpose(), height).transpose();
+    }
+    result
+}
+
+fn total_energy(energy: &Energy, matrix: &Matrix<CustomColor>) -> f32 {
+    energy
+        .color_energy(matrix)
+        .vector
+        .iter()
+        .map(|point| point.value)
+        .sum()
+}
+
+/// Narrows `matrix` to `target_width` with the mix of cropping, seam carving
+/// and uniform scaling that distorts it least. The removed amount is split
+/// in `steps` equal parts between the operators.
+///
+/// Distortion is measured in energy: cropping costs the energy of the
+/// cropped columns, carving the energy of the removed seams and scaling the
+/// energy of the scaled image times the fraction it is squeezed by. Carving
+/// wins small reductions, while large ones move on to scaling and cropping
+/// once the cheap seams run out. Fails on an empty image.
+pub fn retarget_width(
+    matrix: &Matrix<CustomColor>,
+    energy_function: &Energy,
+    target_width: usize,
+    steps: usize,
+) -> Result<(Matrix<CustomColor>, OperatorMix)> {
+    if matrix.vector.is_empty() {
+        return Err(Error::DegenerateImage {
+            width: matrix.width(),
+            height: matrix.height(),
+        });
+    }
+    let target_width = target_width.max(1);
+    if target_width >= matrix.width() {
+        return Ok((matrix.clone(), OperatorMix::default()));
+    }
+
+    let reduction = matrix.width() - target_width;
+    let steps = steps.max(1);
+    let amount = |step: usize| reduction * step / steps;
+    let mut rng = thread_rng();
+    let mut best: Option<(Matrix<CustomColor>, OperatorMix)> = None;
+
+    let energy = energy_function.color_energy(matrix);
+    let matrix_energy = energy.vector.iter().map(|point| point.value).sum::<f32>();
+    for crop_step in 0..=steps {
+        let cropped = amount(crop_step);
+        let (left, kept_energy) = best_crop_window(&energy, matrix.width() - cropped);
+        let crop_distortion = matrix_energy - kept_energy;
+        let mut carved_matrix = crop_columns(matrix, left, matrix.width() - cropped);
+
+        // carve once up to the largest amount and evaluate every smaller
+        // amount on the way there
+        let mut carve_distortion = 0.0;
+        for carve_step in 0..=(steps - crop_step) {
+            let carved = amount(crop_step + carve_step) - cropped;
+            while matrix.width() - cropped - carved_matrix.width() < carved {
+                let (seam, seam_energy) = energy_function
+                    .color_energy(&carved_matrix)
+                    .extract_vertical_seam(&mut rng, false)?;
+                carved_matrix.carve_vertical_seam(&seam)?;
+                carve_distortion += seam_energy;
+            }
+
+            let scaled = carved_matrix.width() - target_width;
+            let scale_distortion = total_energy(energy_function, &carved_matrix) * scaled as f32
+                / carved_matrix.width() as f32;
+            let distortion = crop_distortion + carve_distortion + scale_distortion;
+            if best
+                .as_ref()
+                .is_none_or(|(_, mix)| distortion < mix.distortion)
+            {
+                best = Some((
+                    carved_matrix.clone(),
+                    OperatorMix {
+                        cropped,
+                        carved,
+                        scaled,
+                        distortion,
+                    },
+                ));
+            }
+        }
+    }
+
+    // the loops above evaluate at least one mix, and only the best one is
+    // scaled
+    let (carved_matrix, mix) = best.unwrap();
+    Ok((
+        scale_matrix(&carved_matrix, target_width, carved_matrix.height()),
+        mix,
+    ))
+}
+
+/// `retarget_width` for both dimensions, shrinking the width first. Enlarged
+/// dimensions fall back to seam insertion.
+pub fn retarget(
+    matrix: &Matrix<CustomColor>,
+    energy: &Energy,
+    interpolation: Interpolation,
+    target: &WindowSize,
+    steps: usize,
+) -> Result<(Matrix<CustomColor>, OperatorMix, OperatorMix)> {
+    let (narrowed, width_mix) = retarget_width(matrix, energy, target.width, steps)?;
+    let (lowered, height_mix) =
+        retarget_width(&narrowed.transpose(), energy, target.height, steps)?;
+    let mut result = lowered.transpose();
+    resize_matrix(&mut result, energy, interpolation, target, |_, _| {})?;
+    Ok((result, width_mix, height_mix))
+}
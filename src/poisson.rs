@@ -0,0 +1,224 @@
+use crate::{
+    energy::Energy,
+    error::Result,
+    seam_carver::{next_seam_operation, SeamOperation},
+    structs::{
+        color::CustomColor,
+        matrix::{HorizontalSeam, Matrix, VerticalSeam},
+        window_size::WindowSize,
+    },
+};
+use ::rand::thread_rng;
+
+#[cfg(test)]
+#[path = "tests/poisson.rs"]
+mod poisson_tests;
+
+/// Settings of the Gauss-Seidel solver reconstructing an image from its
+/// gradients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoissonSolver {
+    pub iterations: usize,
+    /// Pull towards the directly carved image, which pins down the constant
+    /// the gradients leave open and keeps colours from drifting.
+    pub data_weight: f32,
+}
+
+impl Default for PoissonSolver {
+    fn default() -> Self {
+        PoissonSolver {
+            iterations: 200,
+            data_weight: 0.01,
+        }
+    }
+}
+
+/// Image carved in the gradient domain: the differences to the right and
+/// lower neighbour of every pixel are carved along with it, and the image is
+/// rebuilt from them afterwards so no new edges appear where seams were.
+#[derive(Clone)]
+pub struct GradientDomainImage {
+    /// The directly carved image, used to find seams and as the data term.
+    pub colors: Matrix<CustomColor>,
+    /// Difference to the right neighbour, zero in the last column.
+    gradient_x: Matrix<[f32; 3]>,
+    /// Difference to the lower neighbour, zero in the last row.
+    gradient_y: Matrix<[f32; 3]>,
+}
+
+fn channels(color: &CustomColor) -> [f32; 3] {
+    [color.r, color.g, color.b]
+}
+
+fn difference(first: [f32; 3], second: [f32; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|channel| second[channel] - first[channel])
+}
+
+impl GradientDomainImage {
+    pub fn new(matrix: &Matrix<CustomColor>) -> Self {
+        let width = matrix.width();
+        let height = matrix.height();
+        let pixel = |row: usize, column: usize| channels(&matrix.vector[row * width + column]);
+        let gradient = |step_x: usize, step_y: usize| {
+            Matrix::new(
+                (0..matrix.vector.len())
+                    .map(|index| {
+                        let (row, column) = (index / width, index % width);
+                        if row + step_y < height && column + step_x < width {
+                            difference(pixel(row, column), pixel(row + step_y, column + step_x))
+                        } else {
+                            [0.0; 3]
+                        }
+                    })
+                    .collect(),
+                width,
+            )
+        };
+
+        GradientDomainImage {
+            colors: matrix.clone(),
+            gradient_x: gradient(1, 0),
+            gradient_y: gradient(0, 1),
+        }
+    }
+
+    /// Removes the seam from the image and both gradient fields. The two
+    /// horizontal differences around a seam pixel are merged into their
+    /// average, so the new neighbours are as far apart as a typical step
+    /// rather than the sum of two.
+    pub fn carve_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        // carving the colours first checks the seam before the gradients
+        // are touched
+        self.colors.carve_vertical_seam(seam)?;
+        let width = self.gradient_x.width();
+        for (row, column) in seam.columns.iter().cloned().enumerate() {
+            let row_vector = &mut self.gradient_x.vector[row * width..(row + 1) * width];
+            if column == width - 1 {
+                if column > 0 {
+                    row_vector[column - 1] = [0.0; 3];
+                }
+            } else if column > 0 {
+                row_vector[column - 1] = [0, 1, 2].map(|channel| {
+                    (row_vector[column - 1][channel] + row_vector[column][channel]) / 2.0
+                });
+            }
+        }
+
+        self.gradient_x.carve_vertical_seam(seam)?;
+        self.gradient_y.carve_vertical_seam(seam)
+    }
+
+    pub fn carve_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        let mut transposed = GradientDomainImage {
+            colors: self.colors.transpose(),
+            gradient_x: self.gradient_y.transpose(),
+            gradient_y: self.gradient_x.transpose(),
+        };
+        transposed.carve_vertical_seam(&VerticalSeam {
+            columns: seam.rows.clone(),
+        })?;
+
+        self.colors = transposed.colors.transpose();
+        self.gradient_x = transposed.gradient_y.transpose();
+        self.gradient_y = transposed.gradient_x.transpose();
+        Ok(())
+    }
+
+    /// Image whose differences best match the carved gradients, found by
+    /// Gauss-Seidel iterations of the screened Poisson equation starting
+    /// from the directly carved image.
+    pub fn reconstruct(&self, solver: &PoissonSolver) -> Matrix<CustomColor> {
+        let width = self.colors.width();
+        let height = self.colors.height();
+        let anchor = self
+            .colors
+            .vector
+            .iter()
+            .map(channels)
+            .collect::<Vec<[f32; 3]>>();
+        let mut result = anchor.clone();
+
+        for _ in 0..solver.iterations {
+            for row in 0..height {
+                for column in 0..width {
+                    let index = row * width + column;
+                    // every neighbour suggests its own value corrected by the
+                    // carved difference between the two pixels
+                    let mut neighbours = Vec::with_capacity(4);
+                    if column > 0 {
+                        neighbours.push((index - 1, self.gradient_x.vector[index - 1], 1.0));
+                    }
+                    if column + 1 < width {
+                        neighbours.push((index + 1, self.gradient_x.vector[index], -1.0));
+                    }
+                    if row > 0 {
+                        neighbours.push((
+                            index - width,
+                            self.gradient_y.vector[index - width],
+                            1.0,
+                        ));
+                    }
+                    if row + 1 < height {
+                        neighbours.push((index + width, self.gradient_y.vector[index], -1.0));
+                    }
+
+                    let weight = neighbours.len() as f32 + solver.data_weight;
+                    result[index] = [0, 1, 2].map(|channel| {
+                        (neighbours
+                            .iter()
+                            .map(|(neighbour, gradient, sign)| {
+                                result[*neighbour][channel] + sign * gradient[channel]
+                            })
+                            .sum::<f32>()
+                            + solver.data_weight * anchor[index][channel])
+                            / weight
+                    });
+                }
+            }
+        }
+
+        Matrix::new(
+            result
+                .into_iter()
+                .zip(self.colors.vector.iter())
+                .map(|(value, color)| CustomColor {
+                    r: value[0].clamp(0.0, 1.0),
+                    g: value[1].clamp(0.0, 1.0),
+                    b: value[2].clamp(0.0, 1.0),
+                    is_inserted: color.is_inserted,
+                })
+                .collect(),
+            width,
+        )
+    }
+}
+
+/// Shrinks `matrix` towards `target` by carving in the gradient domain and
+/// solving for the result once at the end. Smoother than `resize_matrix` on
+/// photographs but much slower; dimensions larger than the image are left as
+/// they are, since there is nothing to carve.
+pub fn carve_gradient_domain(
+    matrix: &Matrix<CustomColor>,
+    energy: &Energy,
+    target: &WindowSize,
+    solver: &PoissonSolver,
+) -> Result<Matrix<CustomColor>> {
+    let target = WindowSize {
+        width: target.width.min(matrix.width()),
+        height: target.height.min(matrix.height()),
+    };
+    let mut rng = thread_rng();
+    let mut image = GradientDomainImage::new(matrix);
+    while let Some(operation) =
+        next_seam_operation(&energy.color_energy(&image.colors), &target, &mut rng)?
+    {
+        match operation {
+            SeamOperation::CarveVertical(seam) => image.carve_vertical_seam(&seam)?,
+            SeamOperation::CarveHorizontal(seam) => image.carve_horizontal_seam(&seam)?,
+            SeamOperation::InsertVertical(_) | SeamOperation::InsertHorizontal(_) => {
+                unreachable!("the target is never larger than the image")
+            }
+        }
+    }
+    Ok(image.reconstruct(solver))
+}
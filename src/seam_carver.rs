@@ -1,88 +1,235 @@
-use crate::{utils::*, WindowSize};
-use ::rand::thread_rng;
+use crate::{
+    energy::Energy,
+    error::{Error, Result},
+    structs::{
+        color::CustomColor,
+        interpolation::Interpolation,
+        layers::LayeredImage,
+        matrix::{HorizontalSeam, Matrix, SeamConstraint, VerticalSeam},
+        pixel::Pixel,
+        target_size::Target,
+        window_size::WindowSize,
+    },
+    utils::*,
+};
+use ::rand::{rngs::ThreadRng, thread_rng};
 use macroquad::texture::Image;
+use serde::{Deserialize, Serialize};
 use std::{
     sync::{Arc, RwLock},
-    thread,
+    thread::{self, JoinHandle},
 };
 
+/// A single step of the resize loop: one seam removed from or inserted into
+/// the image. Serialized as the operation name holding the seam, e.g.
+/// `{"CarveVertical":{"columns":[3,4,4]}}`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SeamOperation {
+    CarveVertical(VerticalSeam),
+    InsertVertical(VerticalSeam),
+    CarveHorizontal(HorizontalSeam),
+    InsertHorizontal(HorizontalSeam),
+}
+
+impl SeamOperation {
+    pub fn apply<T: Pixel>(&self, matrix: &mut Matrix<T>) -> Result<()> {
+        self.apply_interpolated(matrix, Interpolation::default())
+    }
+
+    /// `apply` with inserted pixels filled by `interpolation`.
+    pub fn apply_interpolated<T: Pixel>(
+        &self,
+        matrix: &mut Matrix<T>,
+        interpolation: Interpolation,
+    ) -> Result<()> {
+        self.apply_with_constraint(matrix, interpolation, &SeamConstraint::default())
+    }
+
+    /// `apply_interpolated` for seams found with a looser `constraint`.
+    pub fn apply_with_constraint<T: Pixel>(
+        &self,
+        matrix: &mut Matrix<T>,
+        interpolation: Interpolation,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        match self {
+            SeamOperation::CarveVertical(seam) => {
+                matrix.carve_vertical_seam_with_constraint(seam, constraint)
+            }
+            SeamOperation::InsertVertical(seam) => {
+                matrix.insert_vertical_seam_with_constraint(seam, interpolation, constraint)
+            }
+            SeamOperation::CarveHorizontal(seam) => {
+                matrix.carve_horizontal_seam_with_constraint(seam, constraint)
+            }
+            SeamOperation::InsertHorizontal(seam) => {
+                matrix.insert_horizontal_seam_with_constraint(seam, interpolation, constraint)
+            }
+        }
+    }
+}
+
+/// Picks the next seam to bring the image described by `energy_matrix`
+/// closer to `target`, or `None` once the sizes match. Targets without pixels
+/// are rejected, as are images that would have to shrink below a single row
+/// or column, or to grow from an empty one.
+pub fn next_seam_operation(
+    energy_matrix: &Matrix<GradientMagnitudePoint>,
+    target: &WindowSize,
+    rng: &mut ThreadRng,
+) -> Result<Option<SeamOperation>> {
+    next_seam_operation_with_constraint(energy_matrix, target, &SeamConstraint::default(), rng)
+}
+
+/// `next_seam_operation` with seams shaped by `constraint`.
+pub fn next_seam_operation_with_constraint(
+    energy_matrix: &Matrix<GradientMagnitudePoint>,
+    target: &WindowSize,
+    constraint: &SeamConstraint,
+    rng: &mut ThreadRng,
+) -> Result<Option<SeamOperation>> {
+    if target.width == 0 || target.height == 0 {
+        return Err(Error::DegenerateImage {
+            width: target.width,
+            height: target.height,
+        });
+    }
+    if target.width == energy_matrix.width() && target.height == energy_matrix.height() {
+        return Ok(None);
+    }
+
+    let vertical = |rng: &mut ThreadRng| -> Result<(SeamOperation, f32)> {
+        let carve = target.width < energy_matrix.width();
+        let (seam, energy) =
+            energy_matrix.extract_vertical_seam_with_constraint(rng, !carve, constraint)?;
+        Ok((
+            if carve {
+                SeamOperation::CarveVertical(seam)
+            } else {
+                SeamOperation::InsertVertical(seam)
+            },
+            energy,
+        ))
+    };
+    let horizontal = |rng: &mut ThreadRng| -> Result<(SeamOperation, f32)> {
+        let carve = target.height < energy_matrix.height();
+        let (seam, energy) =
+            energy_matrix.extract_horizontal_seam_with_constraint(rng, !carve, constraint)?;
+        Ok((
+            if carve {
+                SeamOperation::CarveHorizontal(seam)
+            } else {
+                SeamOperation::InsertHorizontal(seam)
+            },
+            energy,
+        ))
+    };
+
+    Ok(Some(if target.height == energy_matrix.height() {
+        vertical(rng)?.0
+    } else if target.width == energy_matrix.width() {
+        horizontal(rng)?.0
+    } else {
+        let (vertical_operation, vertical_seam_energy) = vertical(rng)?;
+        let (horizontal_operation, horizontal_seam_energy) = horizontal(rng)?;
+        if vertical_seam_energy < horizontal_seam_energy {
+            vertical_operation
+        } else {
+            horizontal_operation
+        }
+    }))
+}
+
+/// Runs the resize loop on `energy` to completion, filling inserted pixels
+/// by `interpolation` and calling `on_step` with the image before every seam
+/// operation is applied to it.
+pub fn resize_matrix(
+    matrix: &mut Matrix<CustomColor>,
+    energy: &Energy,
+    interpolation: Interpolation,
+    target: &WindowSize,
+    mut on_step: impl FnMut(&Matrix<CustomColor>, &SeamOperation),
+) -> Result<()> {
+    let mut rng = thread_rng();
+    // the energy may keep state between seams in layers of the image
+    let mut image = LayeredImage::new(std::mem::replace(matrix, Matrix::new(Vec::new(), 0)));
+    image.interpolation = interpolation;
+    let resized = (|| -> Result<()> {
+        energy.prepare(&mut image)?;
+        while let Some(operation) =
+            next_seam_operation(&energy.energy_matrix(&image), target, &mut rng)?
+        {
+            on_step(&image.color, &operation);
+            image.apply(&operation)?;
+        }
+        Ok(())
+    })();
+    *matrix = image.color;
+    resized
+}
+
+/// Resizes `image` in the background towards `target`, which follows
+/// `window_size` unless the target is fixed, showing every step in
+/// `displayed_image`. Fails if the image is empty or the thread cannot be
+/// started; the thread itself only returns if a seam operation fails.
+#[allow(clippy::needless_borrow, clippy::single_match)]
 pub fn spawn_seam_carver(
     displayed_image: &Arc<RwLock<Image>>,
     window_size: &Arc<RwLock<WindowSize>>,
-) {
-    let image_matrix = image_to_matrix(&displayed_image.read().unwrap().clone());
+    mut image: LayeredImage,
+    energy: Energy,
+    target: Target,
+) -> Result<JoinHandle<Result<()>>> {
+    let image_size = WindowSize {
+        height: image.height(),
+        width: image.width(),
+    };
+    if image.color.vector.is_empty() {
+        return Err(Error::DegenerateImage {
+            width: image_size.width,
+            height: image_size.height,
+        });
+    }
+    energy.prepare(&mut image)?;
+    let mut window_size_value = window_size
+        .read()
+        .map_err(|_| Error::Thread("the window size lock is poisoned".to_string()))?
+        .clone();
     let window_size_clone = Arc::clone(&window_size);
     let displayed_image_clone = Arc::clone(&displayed_image);
+
     thread::Builder::new()
         .name("seam_carver".to_string())
         .spawn(move || {
             let mut rng = thread_rng();
-            let mut energy_matrix = gradient_magnitude(&image_matrix);
-            let mut carved_image_matrix = image_matrix.clone();
-            let mut window_size = window_size_clone.read().unwrap().clone();
+            let mut energy_matrix = energy.energy_matrix(&image);
+            let mut carved_image = image;
             loop {
                 match window_size_clone.try_read() {
                     Ok(next_window_size) => {
-                        if *next_window_size != window_size {
-                            window_size = next_window_size.clone();
+                        if *next_window_size != window_size_value {
+                            window_size_value = next_window_size.clone();
                         }
                     }
                     Err(_) => {}
                 };
 
-                if window_size.width == energy_matrix.width()
-                    && window_size.height == energy_matrix.height()
-                {
+                let target_size = target.resolve(&image_size, &window_size_value);
+                let Some(operation) = next_seam_operation(&energy_matrix, &target_size, &mut rng)?
+                else {
                     continue;
-                }
-
-                if window_size.height == energy_matrix.height() {
-                    let carve = window_size.width < energy_matrix.width();
-                    let (seam, _) = energy_matrix.extract_vertical_seam(&mut rng, !carve);
-                    if carve {
-                        carved_image_matrix.carve_vertical_seam(&seam);
-                    } else {
-                        carved_image_matrix.insert_vertical_seam(&seam);
-                    }
-                } else if window_size.width == energy_matrix.width() {
-                    let carve = window_size.height < energy_matrix.height();
-                    let (seam, _) = energy_matrix.extract_horizontal_seam(&mut rng, !carve);
-                    if carve {
-                        carved_image_matrix.carve_horizontal_seam(&seam);
-                    } else {
-                        carved_image_matrix.insert_horizontal_seam(&seam);
-                    }
-                } else {
-                    let carve_vertical = window_size.width < energy_matrix.width();
-                    let (vertical_seam, vertical_seam_energy) =
-                        energy_matrix.extract_vertical_seam(&mut rng, !carve_vertical);
-                    let carve_horizontal = window_size.height < energy_matrix.height();
-                    let (horizontal_seam, horizontal_seam_energy) =
-                        energy_matrix.extract_horizontal_seam(&mut rng, !carve_horizontal);
-                    if vertical_seam_energy < horizontal_seam_energy {
-                        if carve_vertical {
-                            carved_image_matrix.carve_vertical_seam(&vertical_seam);
-                        } else {
-                            carved_image_matrix.insert_vertical_seam(&vertical_seam);
-                        }
-                    } else {
-                        if carve_horizontal {
-                            carved_image_matrix.carve_horizontal_seam(&horizontal_seam);
-                        } else {
-                            carved_image_matrix.insert_horizontal_seam(&horizontal_seam);
-                        }
-                    }
-                }
+                };
+                carved_image.apply(&operation)?;
 
-                energy_matrix = gradient_magnitude(&carved_image_matrix);
+                energy_matrix = energy.energy_matrix(&carved_image);
 
                 match displayed_image_clone.try_write() {
                     Ok(mut display_image_write_lock) => {
-                        *display_image_write_lock = matrix_to_image(&carved_image_matrix);
+                        *display_image_write_lock = matrix_to_image(&carved_image.color);
                     }
                     Err(_) => {}
                 }
             }
         })
-        .unwrap();
+        .map_err(|error| Error::Thread(format!("cannot start the seam carver: {}", error)))
 }
@@ -0,0 +1,365 @@
+use crate::{
+    error::{Error, Result},
+    graph_cut::forward_graph_cut_vertical_seams,
+    structs::{
+        matrix::{HorizontalSeam, Matrix, VerticalSeam},
+        pixel::Pixel,
+    },
+    utils::GradientMagnitudePoint,
+};
+use ::rand::{rngs::ThreadRng, Rng};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+#[path = "tests/seam_finder.rs"]
+mod seam_finder_tests;
+
+/// Strategy for finding the cheapest seam of an energy matrix, so different
+/// solvers can be swapped and compared on the same input. Every finder fails
+/// on an empty matrix.
+pub trait SeamFinder {
+    fn find_vertical_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(VerticalSeam, f32)>;
+    fn find_horizontal_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(HorizontalSeam, f32)>;
+}
+
+/// Row by row dynamic programming, picking randomly between equally cheap
+/// seams.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DynamicProgramming;
+
+impl SeamFinder for DynamicProgramming {
+    fn find_vertical_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(VerticalSeam, f32)> {
+        energy_matrix.extract_vertical_seam(rng, avoid_inserted)
+    }
+    fn find_horizontal_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(HorizontalSeam, f32)> {
+        energy_matrix.extract_horizontal_seam(rng, avoid_inserted)
+    }
+}
+
+fn check_not_empty(energy_matrix: &Matrix<GradientMagnitudePoint>) -> Result<()> {
+    if energy_matrix.vector.is_empty() {
+        return Err(Error::DegenerateImage {
+            width: energy_matrix.width(),
+            height: energy_matrix.height(),
+        });
+    }
+    Ok(())
+}
+
+/// Minimum cut over a pixel graph, see `graph_cut_vertical_seams`. With a
+/// luma matrix the cut uses forward energy instead of the energy matrix, see
+/// `forward_graph_cut_vertical_seams`; the returned total is still the
+/// energy of the seam in the energy matrix, so finders stay comparable.
+#[derive(Clone, Default)]
+pub struct GraphCut {
+    pub forward_energy: Option<Matrix<f32>>,
+}
+
+impl GraphCut {
+    /// Forward energy cut on the luma of `image`, which has to be the image
+    /// the energy matrices are computed from.
+    pub fn forward<T: Pixel>(image: &Matrix<T>) -> Self {
+        GraphCut {
+            forward_energy: Some(Matrix::new(
+                image.vector.iter().map(|pixel| pixel.luma()).collect(),
+                image.width(),
+            )),
+        }
+    }
+
+    fn forward_luma(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+    ) -> Result<Option<&Matrix<f32>>> {
+        match &self.forward_energy {
+            Some(luma)
+                if (luma.width(), luma.height())
+                    != (energy_matrix.width(), energy_matrix.height()) =>
+            {
+                Err(Error::SizeMismatch {
+                    expected: (energy_matrix.width(), energy_matrix.height()),
+                    found: (luma.width(), luma.height()),
+                })
+            }
+            luma => Ok(luma.as_ref()),
+        }
+    }
+}
+
+impl SeamFinder for GraphCut {
+    fn find_vertical_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        _rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(VerticalSeam, f32)> {
+        check_not_empty(energy_matrix)?;
+        let costs = energy_matrix.seam_costs(avoid_inserted);
+        let seam = match self.forward_luma(energy_matrix)? {
+            Some(luma) => forward_graph_cut_vertical_seams(
+                std::slice::from_ref(&costs),
+                std::slice::from_ref(luma),
+            )
+            .pop()
+            .unwrap(),
+            None => costs.graph_cut_vertical_seam(),
+        };
+        let total_energy = energy_matrix.vertical_seam_energy(&seam);
+        Ok((seam, total_energy))
+    }
+    fn find_horizontal_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        _rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(HorizontalSeam, f32)> {
+        check_not_empty(energy_matrix)?;
+        let costs = energy_matrix.seam_costs(avoid_inserted);
+        let seam = match self.forward_luma(energy_matrix)? {
+            Some(luma) => HorizontalSeam {
+                rows: forward_graph_cut_vertical_seams(&[costs.transpose()], &[luma.transpose()])
+                    .pop()
+                    .unwrap()
+                    .columns,
+            },
+            None => costs.graph_cut_horizontal_seam(),
+        };
+        let total_energy = energy_matrix.horizontal_seam_energy(&seam);
+        Ok((seam, total_energy))
+    }
+}
+
+/// Coarse to fine search: the seam is found on a copy of the energy halved
+/// `levels` times and then refined at every finer level by the dynamic
+/// programming of `extract_vertical_seam`, restricted to a band of `band`
+/// pixels around the coarser seam. Much faster on large images, at the risk
+/// of missing thin cheap paths the downscaling averages away.
+#[derive(Clone, Copy, Debug)]
+pub struct Pyramid {
+    pub levels: usize,
+    pub band: usize,
+}
+
+impl Default for Pyramid {
+    fn default() -> Self {
+        Pyramid { levels: 2, band: 2 }
+    }
+}
+
+/// Energy matrix halved in both directions, with every point the mean of the
+/// up to four points it covers.
+fn downscale(energy_matrix: &Matrix<GradientMagnitudePoint>) -> Matrix<GradientMagnitudePoint> {
+    let width = energy_matrix.width();
+    let coarse_width = width.div_ceil(2);
+    let mut sums = vec![(0.0, 0, false); energy_matrix.height().div_ceil(2) * coarse_width];
+    for (index, point) in energy_matrix.vector.iter().enumerate() {
+        let sum = &mut sums[index / width / 2 * coarse_width + index % width / 2];
+        sum.0 += point.value;
+        sum.1 += 1;
+        sum.2 |= point.is_inserted;
+    }
+    Matrix::new(
+        sums.into_iter()
+            .map(|(value, count, is_inserted)| GradientMagnitudePoint {
+                value: value / count as f32,
+                is_inserted,
+            })
+            .collect(),
+        coarse_width,
+    )
+}
+
+impl Pyramid {
+    fn vertical_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        levels: usize,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<VerticalSeam> {
+        let width = energy_matrix.width();
+        if levels == 0 || width < 4 || energy_matrix.height() < 4 {
+            return Ok(energy_matrix.extract_vertical_seam(rng, avoid_inserted)?.0);
+        }
+
+        let coarse = self
+            .vertical_seam(&downscale(energy_matrix), levels - 1, rng, avoid_inserted)?
+            .columns;
+        // columns every row may use: those under the coarse seam in this
+        // and the neighbouring coarse rows, so the bands of neighbouring rows
+        // always overlap and a connected seam exists
+        let bands = (0..energy_matrix.height())
+            .map(|row| {
+                let block = row / 2;
+                let around = &coarse[block.saturating_sub(1)..(block + 2).min(coarse.len())];
+                (
+                    (around.iter().min().unwrap() * 2).saturating_sub(self.band),
+                    (around.iter().max().unwrap() * 2 + 1 + self.band).min(width - 1),
+                )
+            })
+            .collect::<Vec<(usize, usize)>>();
+        let cost = |row: usize, column: usize| {
+            let point = energy_matrix.vector[row * width + column];
+            match (point.is_inserted, avoid_inserted) {
+                (true, true) => f32::INFINITY,
+                (true, false) => 0.0,
+                _ => point.value,
+            }
+        };
+
+        // the dynamic programming of `min_vertical_seam`, over the bands only,
+        // with the values of every row stored from `offsets[row]` on
+        let mut offsets = Vec::with_capacity(bands.len() + 1);
+        offsets.push(0);
+        for (low, high) in bands.iter() {
+            offsets.push(offsets.last().unwrap() + high - low + 1);
+        }
+        let mut dp_result = vec![0.0; *offsets.last().unwrap()];
+        for (row, (low, high)) in bands.iter().cloned().enumerate() {
+            for column in low..=high {
+                let mut value = cost(row, column);
+                if row > 0 {
+                    let (previous_low, previous_high) = bands[row - 1];
+                    let mut previous = f32::INFINITY;
+                    for previous_column in
+                        column.saturating_sub(1).max(previous_low)..=(column + 1).min(previous_high)
+                    {
+                        previous = previous
+                            .min(dp_result[offsets[row - 1] + previous_column - previous_low]);
+                    }
+                    value += previous;
+                }
+                dp_result[offsets[row] + column - low] = value;
+            }
+        }
+        let at = |row: usize, column: usize| dp_result[offsets[row] + column - bands[row].0];
+
+        let last_row = bands.len() - 1;
+        let (low, high) = bands[last_row];
+        let min_value = (low..=high)
+            .map(|column| at(last_row, column))
+            .fold(f32::INFINITY, f32::min);
+        if min_value.is_infinite() {
+            // every path in the bands runs into inserted pixels, so there is
+            // no connected seam to trace back through them
+            return Ok(energy_matrix.extract_vertical_seam(rng, avoid_inserted)?.0);
+        }
+        let min_columns = (low..=high)
+            .filter(|column| at(last_row, *column) == min_value)
+            .collect::<Vec<usize>>();
+        let mut columns = vec![0; bands.len()];
+        columns[last_row] = if min_columns.is_empty() {
+            low
+        } else {
+            min_columns[rng.gen_range(0..min_columns.len())]
+        };
+        for row in (0..last_row).rev() {
+            let (low, high) = bands[row];
+            let next = columns[row + 1];
+            columns[row] = (next.saturating_sub(1).max(low)..=(next + 1).min(high))
+                .reduce(|best, column| {
+                    if at(row, best) < at(row, column) {
+                        best
+                    } else {
+                        column
+                    }
+                })
+                .unwrap();
+        }
+
+        Ok(VerticalSeam { columns })
+    }
+}
+
+impl SeamFinder for Pyramid {
+    fn find_vertical_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(VerticalSeam, f32)> {
+        let seam = self.vertical_seam(energy_matrix, self.levels, rng, avoid_inserted)?;
+        let total_energy = energy_matrix.vertical_seam_energy(&seam);
+        Ok((seam, total_energy))
+    }
+    fn find_horizontal_seam(
+        &self,
+        energy_matrix: &Matrix<GradientMagnitudePoint>,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(HorizontalSeam, f32)> {
+        let seam = HorizontalSeam {
+            rows: self
+                .vertical_seam(&energy_matrix.transpose(), self.levels, rng, avoid_inserted)?
+                .columns,
+        };
+        let total_energy = energy_matrix.horizontal_seam_energy(&seam);
+        Ok((seam, total_energy))
+    }
+}
+
+/// Energy and runtime of the vertical seam found by `finder` next to those
+/// of the exact `DynamicProgramming` seam on the same matrix.
+#[derive(Clone, Copy, Debug)]
+pub struct SeamComparison {
+    pub energy: f32,
+    pub exact_energy: f32,
+    pub duration: Duration,
+    pub exact_duration: Duration,
+}
+
+impl SeamComparison {
+    /// How much more energy the seam has than the exact one, 0.0 for an
+    /// optimal seam.
+    pub fn relative_error(&self) -> f32 {
+        if self.exact_energy > 0.0 {
+            (self.energy - self.exact_energy) / self.exact_energy
+        } else {
+            self.energy
+        }
+    }
+    pub fn speedup(&self) -> f32 {
+        self.exact_duration.as_secs_f32() / self.duration.as_secs_f32().max(f32::EPSILON)
+    }
+}
+
+pub fn compare_with_exact(
+    finder: &impl SeamFinder,
+    energy_matrix: &Matrix<GradientMagnitudePoint>,
+    rng: &mut ThreadRng,
+) -> Result<SeamComparison> {
+    let start = Instant::now();
+    let (_, exact_energy) = DynamicProgramming.find_vertical_seam(energy_matrix, rng, false)?;
+    let exact_duration = start.elapsed();
+
+    let start = Instant::now();
+    let (_, energy) = finder.find_vertical_seam(energy_matrix, rng, false)?;
+    let duration = start.elapsed();
+
+    Ok(SeamComparison {
+        energy,
+        exact_energy,
+        duration,
+        exact_duration,
+    })
+}
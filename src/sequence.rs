@@ -0,0 +1,152 @@
+use crate::{
+    energy::Energy,
+    error::{Error, Result},
+    seam_carver::{resize_matrix, SeamOperation},
+    structs::{
+        color::CustomColor,
+        interpolation::Interpolation,
+        matrix::{Matrix, SeamConstraint},
+        pixel::Pixel,
+        window_size::WindowSize,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+#[cfg(test)]
+#[path = "tests/sequence.rs"]
+mod sequence_tests;
+
+/// Seam operations in the order they were applied to an image of `width`
+/// by `height`, so they can be stored as JSON and replayed later, for example
+/// on a higher bit depth master of the same image. Inserted pixels are
+/// filled by `interpolation` and seams may have the shape `constraint`
+/// allows, both of which are the defaults in files without them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeamSequence {
+    pub width: usize,
+    pub height: usize,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    #[serde(default)]
+    pub constraint: SeamConstraint,
+    pub operations: Vec<SeamOperation>,
+}
+
+/// Size of an image of size `size` after `operation`.
+fn resized(size: &WindowSize, operation: &SeamOperation) -> WindowSize {
+    let mut result = size.clone();
+    match operation {
+        SeamOperation::CarveVertical(_) => result.width -= 1,
+        SeamOperation::InsertVertical(_) => result.width += 1,
+        SeamOperation::CarveHorizontal(_) => result.height -= 1,
+        SeamOperation::InsertHorizontal(_) => result.height += 1,
+    }
+    result
+}
+
+impl SeamSequence {
+    pub fn new(width: usize, height: usize) -> Self {
+        SeamSequence {
+            width,
+            height,
+            interpolation: Interpolation::default(),
+            constraint: SeamConstraint::default(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Resizes `matrix` to `target` like `resize_matrix`, recording every
+    /// seam operation.
+    pub fn record(
+        matrix: &mut Matrix<CustomColor>,
+        energy: &Energy,
+        interpolation: Interpolation,
+        target: &WindowSize,
+    ) -> Result<Self> {
+        let mut sequence = SeamSequence::new(matrix.width(), matrix.height());
+        sequence.interpolation = interpolation;
+        resize_matrix(matrix, energy, interpolation, target, |_, operation| {
+            sequence.operations.push(operation.clone())
+        })?;
+        Ok(sequence)
+    }
+
+    /// Applies the operations to `matrix`, which must have the size the
+    /// sequence was recorded at.
+    pub fn replay<T: Pixel>(&self, matrix: &mut Matrix<T>) -> Result<()> {
+        if matrix.width() != self.width || matrix.height() != self.height {
+            return Err(Error::SizeMismatch {
+                expected: (self.width, self.height),
+                found: (matrix.width(), matrix.height()),
+            });
+        }
+        for operation in &self.operations {
+            operation.apply_with_constraint(matrix, self.interpolation, &self.constraint)?;
+        }
+        Ok(())
+    }
+
+    /// The sequence carried over to the same image at `width` by `height`,
+    /// e.g. from a downscaled proxy to the full resolution master. Every
+    /// operation is scaled up and repeated as often as needed to keep the
+    /// master in proportion with the proxy, so a proxy seam removes a band as
+    /// wide as one proxy pixel from the master.
+    pub fn upscale(&self, width: usize, height: usize) -> SeamSequence {
+        let mut result = SeamSequence::new(width, height);
+        result.interpolation = self.interpolation;
+        result.constraint = self.constraint;
+        let mut proxy = WindowSize {
+            width: self.width,
+            height: self.height,
+        };
+        let mut master = WindowSize { width, height };
+        // master size matching a proxy size, rounded to the nearest pixel
+        let scaled = |proxy_size: usize, from: usize, to: usize| {
+            ((proxy_size * to) as f32 / from as f32).round() as usize
+        };
+
+        for operation in &self.operations {
+            let next_proxy = resized(&proxy, operation);
+            let next_master = WindowSize {
+                width: scaled(next_proxy.width, self.width, width),
+                height: scaled(next_proxy.height, self.height, height),
+            };
+
+            while master != next_master {
+                let step = match operation {
+                    SeamOperation::CarveVertical(seam) => {
+                        SeamOperation::CarveVertical(seam.upscale(&proxy, &master))
+                    }
+                    SeamOperation::InsertVertical(seam) => {
+                        SeamOperation::InsertVertical(seam.upscale(&proxy, &master))
+                    }
+                    SeamOperation::CarveHorizontal(seam) => {
+                        SeamOperation::CarveHorizontal(seam.upscale(&proxy, &master))
+                    }
+                    SeamOperation::InsertHorizontal(seam) => {
+                        SeamOperation::InsertHorizontal(seam.upscale(&proxy, &master))
+                    }
+                };
+                master = resized(&master, &step);
+                result.operations.push(step);
+            }
+            proxy = next_proxy;
+        }
+        result
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        Ok(writer.flush()?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    energy::Energy,
+    error::{Error, Result},
+    structs::{
+        color::CustomColor,
+        matrix::{Matrix, SeamConstraint, VerticalSeam},
+    },
+    utils::*,
+};
+use ::rand::{rngs::ThreadRng, thread_rng};
+
+#[cfg(test)]
+#[path = "tests/stereo.rs"]
+mod stereo_tests;
+
+/// Left and right view of a scene, with the disparity of every left pixel:
+/// the pixel at `column` in the left image shows up at
+/// `column - disparity` in the right one.
+#[derive(Clone)]
+pub struct StereoPair {
+    pub left: Matrix<CustomColor>,
+    pub right: Matrix<CustomColor>,
+    pub disparity: Matrix<f32>,
+}
+
+impl StereoPair {
+    /// Fails if the right view or the disparity map differ in size from the
+    /// left view.
+    pub fn new(
+        left: Matrix<CustomColor>,
+        right: Matrix<CustomColor>,
+        disparity: Matrix<f32>,
+    ) -> Result<Self> {
+        let expected = (left.width(), left.height());
+        for found in [
+            (right.width(), right.height()),
+            (disparity.width(), disparity.height()),
+        ] {
+            if found != expected {
+                return Err(Error::SizeMismatch { expected, found });
+            }
+        }
+        Ok(StereoPair {
+            left,
+            right,
+            disparity,
+        })
+    }
+
+    /// Right image column matching `column` of the left image in `row`,
+    /// clamped to the image.
+    pub fn corresponding_column(&self, row: usize, column: usize) -> usize {
+        let width = self.left.width();
+        let disparity = self.disparity.vector[row * width + column].round() as isize;
+        (column as isize - disparity).clamp(0, width as isize - 1) as usize
+    }
+
+    /// Right image seam removing the pixels matching `left_seam`. It need not
+    /// be connected where the disparity jumps.
+    pub fn corresponding_seam(&self, left_seam: &VerticalSeam) -> VerticalSeam {
+        VerticalSeam {
+            columns: left_seam
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(row, column)| self.corresponding_column(row, *column))
+                .collect(),
+        }
+    }
+
+    /// Cheapest pair of matching seams, left seam first. A left pixel costs
+    /// its own energy plus the energy of its match in the right image, plus
+    /// `disparity_weight` times the disparity gradient so seams avoid cutting
+    /// through depth edges.
+    pub fn extract_vertical_seams(
+        &self,
+        energy: &Energy,
+        disparity_weight: f32,
+        rng: &mut ThreadRng,
+    ) -> Result<(VerticalSeam, VerticalSeam)> {
+        let width = self.left.width();
+        let left_energy = energy.color_energy(&self.left);
+        let right_energy = energy.color_energy(&self.right);
+        let disparity_gradient =
+            gradient_values_with_border(&self.disparity, BorderMode::Replicate);
+
+        let costs = Matrix::new(
+            (0..self.left.vector.len())
+                .map(|index| {
+                    let (row, column) = (index / width, index % width);
+                    left_energy.vector[index].value
+                        + right_energy.vector[row * width + self.corresponding_column(row, column)]
+                            .value
+                        + disparity_weight * disparity_gradient[index]
+                })
+                .collect::<Vec<f32>>(),
+            width,
+        );
+        let left_seam = costs.min_vertical_seam(rng)?;
+        let right_seam = self.corresponding_seam(&left_seam);
+
+        Ok((left_seam, right_seam))
+    }
+
+    /// Removes the seams from both views and from the disparity map, shifting
+    /// the disparities of pixels whose match ends up on the other side of the
+    /// seam. Fails without changing anything if a seam does not fit its view;
+    /// the right seam may jump like `corresponding_seam`.
+    pub fn carve_vertical_seams(
+        &mut self,
+        left_seam: &VerticalSeam,
+        right_seam: &VerticalSeam,
+    ) -> Result<()> {
+        let jumping = SeamConstraint {
+            max_step: self.right.width(),
+            ..SeamConstraint::default()
+        };
+        // every view is carved on a copy and only replaced once all of them
+        // succeeded
+        let mut left = self.left.clone();
+        left.carve_vertical_seam(left_seam)?;
+        let mut right = self.right.clone();
+        right.carve_vertical_seam_with_constraint(right_seam, &jumping)?;
+        let mut disparity = self.disparity.clone();
+
+        let width = disparity.width();
+        for (row, (left_column, right_column)) in left_seam
+            .columns
+            .iter()
+            .zip(right_seam.columns.iter())
+            .enumerate()
+        {
+            for column in 0..width {
+                let disparity = &mut disparity.vector[row * width + column];
+                let target = column as isize - disparity.round() as isize;
+                if target > *right_column as isize {
+                    *disparity += 1.0;
+                }
+                if column > *left_column {
+                    *disparity -= 1.0;
+                }
+            }
+        }
+
+        disparity.carve_vertical_seam(left_seam)?;
+
+        self.left = left;
+        self.right = right;
+        self.disparity = disparity;
+        Ok(())
+    }
+
+    /// Narrows both views to `target_width` one matching seam pair at a time.
+    pub fn retarget_width(
+        &mut self,
+        energy: &Energy,
+        target_width: usize,
+        disparity_weight: f32,
+    ) -> Result<()> {
+        let mut rng = thread_rng();
+        while self.left.width() > target_width.max(1) {
+            let (left_seam, right_seam) =
+                self.extract_vertical_seams(energy, disparity_weight, &mut rng)?;
+            self.carve_vertical_seams(&left_seam, &right_seam)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+use crate::{
+    error::{Error, Result},
+    structs::{color::CustomColor, matrix::VerticalSeam, pixel::Pixel},
+    utils::BorderMode,
+};
+use ::image::{
+    error::{
+        DecodingError, EncodingError, ImageFormatHint, UnsupportedError, UnsupportedErrorKind,
+    },
+    ImageError, ImageFormat,
+};
+use ::rand::{thread_rng, Rng};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+#[path = "tests/streaming.rs"]
+mod streaming_tests;
+
+/// Image read one scanline at a time from the top, so it never has to be in
+/// memory as a whole.
+pub trait RowSource {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn next_row(&mut self) -> Result<Vec<CustomColor>>;
+}
+
+fn png_decoding_error(error: png::DecodingError) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Png),
+        error,
+    ))
+}
+
+fn png_encoding_error(error: png::EncodingError) -> ImageError {
+    ImageError::Encoding(EncodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Png),
+        error,
+    ))
+}
+
+/// Scanlines of a non-interlaced PNG file, converted to 8-bit colour.
+pub struct PngRows {
+    reader: png::Reader<BufReader<File>>,
+    channels: usize,
+}
+
+impl PngRows {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let reader = decoder.read_info().map_err(png_decoding_error)?;
+        if reader.info().interlaced {
+            return Err(ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Exact(ImageFormat::Png),
+                "interlaced images cannot be read row by row",
+            ))
+            .into());
+        }
+        let channels = reader.output_color_type().0.samples();
+        Ok(PngRows { reader, channels })
+    }
+}
+
+impl RowSource for PngRows {
+    fn width(&self) -> usize {
+        self.reader.info().width as usize
+    }
+    fn height(&self) -> usize {
+        self.reader.info().height as usize
+    }
+    fn next_row(&mut self) -> Result<Vec<CustomColor>> {
+        let channels = self.channels;
+        let row =
+            self.reader
+                .next_row()
+                .map_err(png_decoding_error)?
+                .ok_or(ImageError::IoError(
+                    std::io::ErrorKind::UnexpectedEof.into(),
+                ))?;
+        Ok(row
+            .data()
+            .chunks_exact(channels)
+            .map(|pixel| {
+                // grayscale images have a single colour sample
+                let (r, g, b) = if channels < 3 {
+                    (pixel[0], pixel[0], pixel[0])
+                } else {
+                    (pixel[0], pixel[1], pixel[2])
+                };
+                CustomColor {
+                    r: r as f32 / 255.0,
+                    g: g as f32 / 255.0,
+                    b: b as f32 / 255.0,
+                    is_inserted: false,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Tiles of equal height read side by side as one wide image.
+pub struct StitchedRows {
+    tiles: Vec<Box<dyn RowSource>>,
+}
+
+impl StitchedRows {
+    /// Returns `None` if the tiles differ in height or there are none.
+    pub fn new(tiles: Vec<Box<dyn RowSource>>) -> Option<Self> {
+        let height = tiles.first()?.height();
+        if tiles.iter().any(|tile| tile.height() != height) {
+            return None;
+        }
+        Some(StitchedRows { tiles })
+    }
+}
+
+impl RowSource for StitchedRows {
+    fn width(&self) -> usize {
+        self.tiles.iter().map(|tile| tile.width()).sum()
+    }
+    fn height(&self) -> usize {
+        self.tiles[0].height()
+    }
+    fn next_row(&mut self) -> Result<Vec<CustomColor>> {
+        let mut row = Vec::with_capacity(self.width());
+        for tile in self.tiles.iter_mut() {
+            row.extend(tile.next_row()?);
+        }
+        Ok(row)
+    }
+}
+
+/// Working copy between seams: the width and height as `u32`, then the
+/// channels of every pixel as little endian `f32`, so nothing is lost to
+/// quantization between passes.
+struct RawRows {
+    reader: BufReader<File>,
+    width: usize,
+    height: usize,
+}
+
+impl RawRows {
+    fn open(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        Ok(RawRows {
+            reader,
+            width: u32::from_le_bytes(header[..4].try_into().unwrap()) as usize,
+            height: u32::from_le_bytes(header[4..].try_into().unwrap()) as usize,
+        })
+    }
+}
+
+impl RowSource for RawRows {
+    fn width(&self) -> usize {
+        self.width
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+    fn next_row(&mut self) -> Result<Vec<CustomColor>> {
+        let mut bytes = vec![0; self.width * 12];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes
+            .chunks_exact(12)
+            .map(|pixel| {
+                let channel =
+                    |index: usize| f32::from_le_bytes(pixel[index..index + 4].try_into().unwrap());
+                CustomColor {
+                    r: channel(0),
+                    g: channel(4),
+                    b: channel(8),
+                    is_inserted: false,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Energy of one row with the rows above and below it, matching
+/// `gradient_magnitude_with_border`. The rows missing at the top and bottom
+/// are made up from the rows at hand, which works for every border but
+/// `BorderMode::Wrap`.
+fn row_energy(
+    above: Option<&[CustomColor]>,
+    row: &[CustomColor],
+    below: Option<&[CustomColor]>,
+    border: BorderMode,
+) -> Vec<f32> {
+    let (above, below) = match border {
+        BorderMode::Replicate => (above.or(Some(row)), below.or(Some(row))),
+        // the row mirrored into the border is the one on the other side
+        BorderMode::Reflect => (above.or(below).or(Some(row)), below.or(above).or(Some(row))),
+        BorderMode::Zero | BorderMode::Wrap => (above, below),
+    };
+    let luma =
+        |row: Option<&[CustomColor]>, column: usize| row.map_or(0.0, |row| row[column].luma());
+    let horizontal = |column: usize, offset: isize| {
+        border
+            .neighbour(column, offset, row.len())
+            .map_or(0.0, |column| row[column].luma())
+    };
+    (0..row.len())
+        .map(|column| {
+            let (left, right) = (horizontal(column, -1), horizontal(column, 1));
+            ((luma(above, column) - luma(below, column)).powi(2) + (left - right).powi(2)).sqrt()
+        })
+        .collect()
+}
+
+/// Cheapest vertical seam of the streamed image on the gradient magnitude
+/// with `border`. Only the previous row of the dynamic programming is kept
+/// in memory; the step taken in every row is written to `backtrack_path`,
+/// one byte per pixel, and read back backwards. Wrapping borders need the
+/// last row before the first one and are not supported, and images without
+/// pixels have no seam.
+pub fn streamed_vertical_seam(
+    source: &mut dyn RowSource,
+    border: BorderMode,
+    backtrack_path: &Path,
+) -> Result<VerticalSeam> {
+    if border == BorderMode::Wrap {
+        return Err(
+            ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+                ImageFormatHint::Unknown,
+                UnsupportedErrorKind::GenericFeature("wrapping borders when streaming".to_string()),
+            ))
+            .into(),
+        );
+    }
+    let (width, height) = (source.width(), source.height());
+    if width == 0 || height == 0 {
+        return Err(Error::DegenerateImage { width, height });
+    }
+    let mut backtrack = BufWriter::new(File::create(backtrack_path)?);
+    let mut previous_costs: Option<Vec<f32>> = None;
+    let mut above: Option<Vec<CustomColor>> = None;
+    let mut row = source.next_row()?;
+
+    for row_index in 0..height {
+        let below = if row_index + 1 < height {
+            Some(source.next_row()?)
+        } else {
+            None
+        };
+        let energy = row_energy(above.as_deref(), &row, below.as_deref(), border);
+
+        let mut steps = vec![1u8; width];
+        let costs = match &previous_costs {
+            None => energy,
+            Some(previous_costs) => energy
+                .into_iter()
+                .enumerate()
+                .map(|(column, value)| {
+                    // the rightmost of the cheapest predecessors, like
+                    // `min_vertical_seam`
+                    let mut best = column;
+                    for previous in column.saturating_sub(1)..=(column + 1).min(width - 1) {
+                        if previous_costs[previous] <= previous_costs[best] {
+                            best = previous;
+                        }
+                    }
+                    steps[column] = (best + 1 - column) as u8;
+                    value + previous_costs[best]
+                })
+                .collect(),
+        };
+        backtrack.write_all(&steps)?;
+
+        previous_costs = Some(costs);
+        above = Some(row);
+        let Some(next) = below else {
+            break;
+        };
+        row = next;
+    }
+    backtrack.flush()?;
+    drop(backtrack);
+
+    let last_costs = previous_costs.unwrap();
+    let min_cost = last_costs.iter().cloned().fold(f32::INFINITY, f32::min);
+    let min_columns = (0..width)
+        .filter(|column| last_costs[*column] == min_cost)
+        .collect::<Vec<usize>>();
+    let mut columns = vec![0; height];
+    columns[height - 1] = min_columns[thread_rng().gen_range(0..min_columns.len())];
+
+    let mut backtrack = File::open(backtrack_path)?;
+    for row_index in (1..height).rev() {
+        let mut step = [0];
+        backtrack.seek(SeekFrom::Start(
+            (row_index * width + columns[row_index]) as u64,
+        ))?;
+        backtrack.read_exact(&mut step)?;
+        columns[row_index - 1] = columns[row_index] + step[0] as usize - 1;
+    }
+    Ok(VerticalSeam { columns })
+}
+
+/// Narrows the image `open_source` reads to `target_width` with seams found
+/// like `streamed_vertical_seam`, one seam per two passes over the rows, and
+/// hands the rows of the result to `write_row`. Images already that narrow
+/// are passed on as they are and a zero width counts as one. Only a few rows
+/// are held in memory at a time; the intermediate images live in
+/// `work_directory`, which needs room for about two copies of the image.
+pub fn narrow_streamed(
+    open_source: impl Fn() -> Result<Box<dyn RowSource>>,
+    target_width: usize,
+    border: BorderMode,
+    work_directory: &Path,
+    mut write_row: impl FnMut(&[CustomColor]) -> Result<()>,
+) -> Result<()> {
+    fs::create_dir_all(work_directory)?;
+    let backtrack_path = work_directory.join("backtrack.bin");
+    let pass_paths = [
+        work_directory.join("pass_0.bin"),
+        work_directory.join("pass_1.bin"),
+    ];
+    // the original image until the first seam is gone, then the copy written
+    // by the last pass
+    let open = |pass: Option<usize>| -> Result<Box<dyn RowSource>> {
+        match pass {
+            None => open_source(),
+            Some(pass) => Ok(Box::new(RawRows::open(&pass_paths[pass % 2])?)),
+        }
+    };
+
+    let mut current = None;
+    loop {
+        let mut source = open(current)?;
+        if source.width() <= target_width.max(1) {
+            for _ in 0..source.height() {
+                write_row(&source.next_row()?)?;
+            }
+            break;
+        }
+        let seam = streamed_vertical_seam(source.as_mut(), border, &backtrack_path)?;
+
+        // read the image again, writing it out without the seam
+        let mut source = open(current)?;
+        let pass = current.map_or(0, |pass| pass + 1);
+        let mut output = BufWriter::new(File::create(&pass_paths[pass % 2])?);
+        output.write_all(&(source.width() as u32 - 1).to_le_bytes())?;
+        output.write_all(&(source.height() as u32).to_le_bytes())?;
+        for column in seam.columns {
+            let mut row = source.next_row()?;
+            row.remove(column);
+            for pixel in row {
+                for channel in [pixel.r, pixel.g, pixel.b] {
+                    output.write_all(&channel.to_le_bytes())?;
+                }
+            }
+        }
+        output.flush()?;
+        current = Some(pass);
+    }
+
+    for path in pass_paths.iter().chain([&backtrack_path]) {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Narrows the PNG tiles in `tiles`, stitched left to right, to
+/// `target_width` like `narrow_streamed` with the gradient magnitude on
+/// `border` and writes the result to the PNG file `output` without ever
+/// loading the whole image.
+pub fn narrow_png_streamed(
+    tiles: &[PathBuf],
+    output: &Path,
+    target_width: usize,
+    border: BorderMode,
+    work_directory: &Path,
+) -> Result<()> {
+    let open_source = || -> Result<Box<dyn RowSource>> {
+        let tiles = tiles
+            .iter()
+            .map(|path| Ok(Box::new(PngRows::open(path)?) as Box<dyn RowSource>))
+            .collect::<Result<Vec<Box<dyn RowSource>>>>()?;
+        Ok(Box::new(StitchedRows::new(tiles).ok_or(
+            ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Exact(ImageFormat::Png),
+                "tiles must be present and of the same height",
+            )),
+        )?))
+    };
+    let source = open_source()?;
+    // the width `narrow_streamed` stops at
+    let width = target_width.max(1).min(source.width());
+
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(output)?),
+        width as u32,
+        source.height() as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(png_encoding_error)?
+        .into_stream_writer()
+        .map_err(png_encoding_error)?;
+
+    narrow_streamed(open_source, width, border, work_directory, |row| {
+        let bytes = row
+            .iter()
+            .flat_map(|color| {
+                [color.r, color.g, color.b]
+                    .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+                    .into_iter()
+                    .chain([255])
+            })
+            .collect::<Vec<u8>>();
+        Ok(writer.write_all(&bytes)?)
+    })?;
+    Ok(writer.finish().map_err(png_encoding_error)?)
+}
@@ -1,3 +1,5 @@
+use crate::structs::pixel::Pixel;
+
 #[derive(Clone, Copy, Debug)]
 pub struct CustomColor {
     pub r: f32,
@@ -11,3 +13,75 @@ impl PartialEq for CustomColor {
         self.r == other.r && self.g == other.g && self.b == other.b
     }
 }
+
+impl CustomColor {
+    /// CIELAB coordinates of the colour, treating `r`, `g` and `b` as sRGB in
+    /// the `0.0..=1.0` range and using the D65 white point.
+    pub fn to_lab(&self) -> [f32; 3] {
+        let linear = |channel: f32| {
+            if channel <= 0.04045 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(self.r), linear(self.g), linear(self.b));
+        let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+        let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+        let z = (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / 1.08883;
+
+        let f = |t: f32| {
+            const DELTA: f32 = 6.0 / 29.0;
+            if t > DELTA.powi(3) {
+                t.cbrt()
+            } else {
+                t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+    }
+}
+
+impl Pixel for CustomColor {
+    fn luma(&self) -> f32 {
+        0.299 * self.r + 0.587 * self.g + 0.114 * self.b
+    }
+    fn average(pixels: &[Self]) -> Self {
+        let sum = pixels.iter().fold((0.0, 0.0, 0.0), |acc, value| {
+            (acc.0 + value.r, acc.1 + value.g, acc.2 + value.b)
+        });
+        let count = pixels.len() as f32;
+        CustomColor {
+            r: sum.0 / count,
+            g: sum.1 / count,
+            b: sum.2 / count,
+            is_inserted: false,
+        }
+    }
+    fn blend(pixels: &[Self], weights: &[f32]) -> Self {
+        let sum =
+            pixels
+                .iter()
+                .zip(weights.iter())
+                .fold((0.0, 0.0, 0.0), |acc, (value, weight)| {
+                    (
+                        acc.0 + value.r * weight,
+                        acc.1 + value.g * weight,
+                        acc.2 + value.b * weight,
+                    )
+                });
+        CustomColor {
+            r: sum.0.clamp(0.0, 1.0),
+            g: sum.1.clamp(0.0, 1.0),
+            b: sum.2.clamp(0.0, 1.0),
+            is_inserted: false,
+        }
+    }
+    fn mark_inserted(&mut self) {
+        self.is_inserted = true;
+    }
+    fn is_inserted(&self) -> bool {
+        self.is_inserted
+    }
+}
@@ -0,0 +1,372 @@
+use crate::{
+    error::Result,
+    structs::{
+        color::CustomColor,
+        matrix::{validate_path, HorizontalSeam, Matrix, SeamConstraint, VerticalSeam},
+        pixel::Pixel,
+    },
+    utils::gradient_values,
+};
+use ::rand::rngs::ThreadRng;
+
+#[cfg(test)]
+#[path = "../tests/compact.rs"]
+mod compact_tests;
+
+/// 8-bit RGBA pixel, a quarter of the size of `CustomColor`. Inserted flags
+/// are kept in an `InsertedMask` next to the pixels instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompactColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Pixel for CompactColor {
+    fn luma(&self) -> f32 {
+        (0.299 * self.r as f32 + 0.587 * self.g as f32 + 0.114 * self.b as f32) / 255.0
+    }
+    fn average(pixels: &[Self]) -> Self {
+        let sum = pixels.iter().fold((0, 0, 0, 0), |acc, value| {
+            (
+                acc.0 + value.r as u32,
+                acc.1 + value.g as u32,
+                acc.2 + value.b as u32,
+                acc.3 + value.a as u32,
+            )
+        });
+        let count = pixels.len() as u32;
+        CompactColor {
+            r: ((sum.0 + count / 2) / count) as u8,
+            g: ((sum.1 + count / 2) / count) as u8,
+            b: ((sum.2 + count / 2) / count) as u8,
+            a: ((sum.3 + count / 2) / count) as u8,
+        }
+    }
+    fn blend(pixels: &[Self], weights: &[f32]) -> Self {
+        let sum = pixels
+            .iter()
+            .zip(weights.iter())
+            .fold([0.0; 4], |acc, (value, weight)| {
+                [
+                    acc[0] + value.r as f32 * weight,
+                    acc[1] + value.g as f32 * weight,
+                    acc[2] + value.b as f32 * weight,
+                    acc[3] + value.a as f32 * weight,
+                ]
+            })
+            .map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+        CompactColor {
+            r: sum[0],
+            g: sum[1],
+            b: sum[2],
+            a: sum[3],
+        }
+    }
+}
+
+impl From<CustomColor> for CompactColor {
+    fn from(color: CustomColor) -> Self {
+        CompactColor {
+            r: (color.r.clamp(0.0, 1.0) * 255.0).round() as u8,
+            g: (color.g.clamp(0.0, 1.0) * 255.0).round() as u8,
+            b: (color.b.clamp(0.0, 1.0) * 255.0).round() as u8,
+            a: 255,
+        }
+    }
+}
+
+impl From<CompactColor> for CustomColor {
+    fn from(color: CompactColor) -> Self {
+        CustomColor {
+            r: color.r as f32 / 255.0,
+            g: color.g as f32 / 255.0,
+            b: color.b as f32 / 255.0,
+            is_inserted: false,
+        }
+    }
+}
+
+/// Gradient magnitude quantized to 16 bits over its `0.0..=SQRT_2` range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CompactEnergy(pub u16);
+
+impl CompactEnergy {
+    pub fn from_value(value: f32) -> Self {
+        CompactEnergy(
+            (value / std::f32::consts::SQRT_2 * u16::MAX as f32)
+                .round()
+                .clamp(0.0, u16::MAX as f32) as u16,
+        )
+    }
+    pub fn value(&self) -> f32 {
+        self.0 as f32 / u16::MAX as f32 * std::f32::consts::SQRT_2
+    }
+}
+
+/// One bit per pixel marking pixels that belong to, or border, an inserted
+/// seam. Follows the same seams as the pixel matrix it describes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InsertedMask {
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+}
+
+impl InsertedMask {
+    pub fn new(width: usize, height: usize) -> Self {
+        InsertedMask {
+            width,
+            height,
+            bits: vec![0; (width * height).div_ceil(64)],
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        let index = row * self.width + column;
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+    pub fn set(&mut self, row: usize, column: usize, value: bool) {
+        let index = row * self.width + column;
+        if value {
+            self.bits[index / 64] |= 1 << (index % 64);
+        } else {
+            self.bits[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    fn rebuild(&mut self, width: usize, height: usize, bit: impl Fn(usize, usize) -> bool) {
+        let mut result = InsertedMask::new(width, height);
+        for row in 0..height {
+            for column in 0..width {
+                if bit(row, column) {
+                    result.set(row, column, true);
+                }
+            }
+        }
+        *self = result;
+    }
+
+    pub fn carve_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        validate_path(
+            &seam.columns,
+            self.height,
+            self.width,
+            &SeamConstraint::default(),
+        )?;
+        let old = self.clone();
+        self.rebuild(self.width - 1, self.height, |row, column| {
+            if column < seam.columns[row] {
+                old.get(row, column)
+            } else {
+                old.get(row, column + 1)
+            }
+        });
+        Ok(())
+    }
+    pub fn carve_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        validate_path(
+            &seam.rows,
+            self.width,
+            self.height,
+            &SeamConstraint::default(),
+        )?;
+        let old = self.clone();
+        self.rebuild(self.width, self.height - 1, |row, column| {
+            if row < seam.rows[column] {
+                old.get(row, column)
+            } else {
+                old.get(row + 1, column)
+            }
+        });
+        Ok(())
+    }
+    pub fn insert_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        validate_path(
+            &seam.columns,
+            self.height,
+            self.width,
+            &SeamConstraint::default(),
+        )?;
+        let old = self.clone();
+        self.rebuild(self.width + 1, self.height, |row, column| {
+            let seam_column = seam.columns[row];
+            if column < seam_column {
+                old.get(row, column)
+            } else if column <= seam_column + 2 {
+                true
+            } else {
+                old.get(row, column - 1)
+            }
+        });
+        Ok(())
+    }
+    pub fn insert_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        validate_path(
+            &seam.rows,
+            self.width,
+            self.height,
+            &SeamConstraint::default(),
+        )?;
+        let old = self.clone();
+        self.rebuild(self.width, self.height + 1, |row, column| {
+            let seam_row = seam.rows[column];
+            if row < seam_row {
+                old.get(row, column)
+            } else if row <= seam_row + 2 {
+                true
+            } else {
+                old.get(row - 1, column)
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Cache-friendlier alternative to `Matrix<CustomColor>`: 4 bytes per pixel
+/// plus one bit for the inserted flag instead of 16 bytes.
+#[derive(Clone)]
+pub struct CompactImage {
+    pub pixels: Matrix<CompactColor>,
+    pub inserted: InsertedMask,
+}
+
+impl CompactImage {
+    pub fn new(pixels: Matrix<CompactColor>) -> Self {
+        let inserted = InsertedMask::new(pixels.width(), pixels.height());
+        CompactImage { pixels, inserted }
+    }
+    pub fn from_matrix(matrix: &Matrix<CustomColor>) -> Self {
+        let mut result = CompactImage::new(Matrix::new(
+            matrix
+                .vector
+                .iter()
+                .cloned()
+                .map(CompactColor::from)
+                .collect(),
+            matrix.width(),
+        ));
+        for (index, color) in matrix.vector.iter().enumerate() {
+            if color.is_inserted {
+                result
+                    .inserted
+                    .set(index / matrix.width(), index % matrix.width(), true);
+            }
+        }
+        result
+    }
+    pub fn to_matrix(&self) -> Matrix<CustomColor> {
+        let width = self.pixels.width();
+        Matrix::new(
+            self.pixels
+                .vector
+                .iter()
+                .enumerate()
+                .map(|(index, color)| CustomColor {
+                    is_inserted: self.inserted.get(index / width, index % width),
+                    ..CustomColor::from(*color)
+                })
+                .collect(),
+            width,
+        )
+    }
+    pub fn width(&self) -> usize {
+        self.pixels.width()
+    }
+    pub fn height(&self) -> usize {
+        self.pixels.height()
+    }
+    pub fn energy(&self) -> Matrix<CompactEnergy> {
+        Matrix::new(
+            gradient_values(&self.pixels)
+                .into_iter()
+                .map(CompactEnergy::from_value)
+                .collect(),
+            self.pixels.width(),
+        )
+    }
+
+    /// The mask has the size of the pixels, so it accepts every seam they
+    /// accept.
+    pub fn carve_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        self.pixels.carve_vertical_seam(seam)?;
+        self.inserted.carve_vertical_seam(seam)
+    }
+    pub fn carve_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        self.pixels.carve_horizontal_seam(seam)?;
+        self.inserted.carve_horizontal_seam(seam)
+    }
+    pub fn insert_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        self.pixels.insert_vertical_seam(seam)?;
+        self.inserted.insert_vertical_seam(seam)
+    }
+    pub fn insert_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        self.pixels.insert_horizontal_seam(seam)?;
+        self.inserted.insert_horizontal_seam(seam)
+    }
+}
+
+impl Matrix<CompactEnergy> {
+    fn seam_costs(&self, inserted: &InsertedMask, avoid_inserted: bool) -> Matrix<f32> {
+        Matrix::new(
+            self.vector
+                .iter()
+                .enumerate()
+                .map(|(index, energy)| {
+                    if inserted.get(index / self.width(), index % self.width()) {
+                        if avoid_inserted {
+                            f32::INFINITY
+                        } else {
+                            0.0
+                        }
+                    } else {
+                        energy.value()
+                    }
+                })
+                .collect(),
+            self.width(),
+        )
+    }
+    pub fn extract_vertical_seam(
+        &self,
+        rng: &mut ThreadRng,
+        inserted: &InsertedMask,
+        avoid_inserted: bool,
+    ) -> Result<(VerticalSeam, f32)> {
+        let seam = self
+            .seam_costs(inserted, avoid_inserted)
+            .min_vertical_seam(rng)?;
+        let total_energy = seam
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(row, column)| self.vector[self.width() * row + column].value())
+            .sum();
+
+        Ok((seam, total_energy))
+    }
+    pub fn extract_horizontal_seam(
+        &self,
+        rng: &mut ThreadRng,
+        inserted: &InsertedMask,
+        avoid_inserted: bool,
+    ) -> Result<(HorizontalSeam, f32)> {
+        let seam = self
+            .seam_costs(inserted, avoid_inserted)
+            .min_horizontal_seam(rng)?;
+        let total_energy = seam
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(column, row)| self.vector[self.width() * row + column].value())
+            .sum();
+
+        Ok((seam, total_energy))
+    }
+}
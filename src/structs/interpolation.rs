@@ -0,0 +1,59 @@
+use crate::structs::pixel::Pixel;
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "../tests/interpolation.rs"]
+mod interpolation_tests;
+
+/// How the pixel inserted after a seam pixel, between it and its next
+/// neighbour, is computed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Mean of the seam pixel and its direct neighbours on both sides.
+    #[default]
+    Average,
+    /// Copy of the seam pixel, which keeps edges fully sharp.
+    Duplicate,
+    /// Midpoint between the seam pixel and its next neighbour.
+    Linear,
+    /// Catmull-Rom spline through the two pixels on either side, evaluated
+    /// halfway between the seam pixel and its next neighbour. Only the line
+    /// across the seam is used, not a 2-D bicubic patch.
+    CatmullRom,
+    /// Half a step extrapolated from the smoother side of the gap, so the
+    /// local gradient continues there and the edge on the other side is not
+    /// blurred.
+    GradientPreserving,
+}
+
+impl Interpolation {
+    /// Neighbours needed on either side of the seam pixel.
+    pub const RADIUS: usize = 2;
+
+    /// Pixel to insert after `neighbours[index]`, with the neighbours out of
+    /// the image replaced by the nearest one in it.
+    pub fn fill<T: Pixel>(&self, neighbours: &[T], index: usize) -> T {
+        let tap = |offset: isize| {
+            neighbours[(index as isize + offset).clamp(0, neighbours.len() as isize - 1) as usize]
+        };
+
+        match self {
+            Interpolation::Average => {
+                T::average(&neighbours[index.saturating_sub(1)..(index + 2).min(neighbours.len())])
+            }
+            Interpolation::Duplicate => tap(0),
+            Interpolation::Linear => T::average(&[tap(0), tap(1)]),
+            Interpolation::CatmullRom => T::blend(
+                &[tap(-1), tap(0), tap(1), tap(2)],
+                &[-1.0 / 16.0, 9.0 / 16.0, 9.0 / 16.0, -1.0 / 16.0],
+            ),
+            Interpolation::GradientPreserving => {
+                if (tap(0).luma() - tap(-1).luma()).abs() <= (tap(2).luma() - tap(1).luma()).abs() {
+                    T::blend(&[tap(0), tap(-1)], &[1.5, -0.5])
+                } else {
+                    T::blend(&[tap(1), tap(2)], &[1.5, -0.5])
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{
+    error::{Error, Result},
+    seam_carver::SeamOperation,
+    structs::{
+        color::CustomColor,
+        interpolation::Interpolation,
+        matrix::{HorizontalSeam, Matrix, SeamConstraint, VerticalSeam},
+        pixel::Pixel,
+    },
+};
+use std::any::Any;
+
+#[cfg(test)]
+#[path = "../tests/layers.rs"]
+mod layers_tests;
+
+/// How the pixels of an inserted seam are filled in an auxiliary layer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InsertionPolicy {
+    /// Mean of the seam pixel and its neighbours, for continuous data such as
+    /// depth, normals or UV coordinates.
+    Average,
+    /// Most common value among the seam pixel and its neighbours, so discrete
+    /// data such as segmentation labels never gets new values.
+    Majority,
+    /// Duplicate of the seam pixel.
+    Copy,
+}
+
+/// A matrix carried along with the colour image, filled according to its
+/// insertion policy when seams are inserted.
+pub struct Layer<T> {
+    pub matrix: Matrix<T>,
+    policy: InsertionPolicy,
+    fill: fn(&[T], usize) -> T,
+}
+
+impl<T> Layer<T>
+where
+    T: Clone + std::marker::Send + Sync + Copy,
+{
+    pub fn copied(matrix: Matrix<T>) -> Self {
+        Layer {
+            matrix,
+            policy: InsertionPolicy::Copy,
+            fill: |neighbours, seam_index| neighbours[seam_index],
+        }
+    }
+    pub fn policy(&self) -> InsertionPolicy {
+        self.policy
+    }
+}
+
+impl<T: Pixel> Layer<T> {
+    pub fn averaged(matrix: Matrix<T>) -> Self {
+        Layer {
+            matrix,
+            policy: InsertionPolicy::Average,
+            fill: |neighbours, _| T::average(neighbours),
+        }
+    }
+}
+
+impl<T> Layer<T>
+where
+    T: Clone + std::marker::Send + Sync + Copy + PartialEq,
+{
+    pub fn majority(matrix: Matrix<T>) -> Self {
+        Layer {
+            matrix,
+            policy: InsertionPolicy::Majority,
+            fill: |neighbours, seam_index| {
+                // ties go to the seam pixel, which is checked first
+                std::iter::once(neighbours[seam_index])
+                    .chain(neighbours.iter().cloned())
+                    .min_by_key(|candidate| {
+                        std::cmp::Reverse(
+                            neighbours
+                                .iter()
+                                .filter(|neighbour| *neighbour == candidate)
+                                .count(),
+                        )
+                    })
+                    .unwrap()
+            },
+        }
+    }
+}
+
+/// Type erased view of a `Layer<T>` so layers of different types can share
+/// one container. Seams are validated against `constraint`, the one they
+/// were found with.
+pub trait SeamLayer: Send + Sync {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn carve_vertical_seam(
+        &mut self,
+        seam: &VerticalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()>;
+    fn carve_horizontal_seam(
+        &mut self,
+        seam: &HorizontalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()>;
+    fn insert_vertical_seam(
+        &mut self,
+        seam: &VerticalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()>;
+    fn insert_horizontal_seam(
+        &mut self,
+        seam: &HorizontalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T> SeamLayer for Layer<T>
+where
+    T: Clone + std::marker::Send + Sync + Copy + 'static,
+{
+    fn width(&self) -> usize {
+        self.matrix.width()
+    }
+    fn height(&self) -> usize {
+        self.matrix.height()
+    }
+    fn carve_vertical_seam(
+        &mut self,
+        seam: &VerticalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        self.matrix
+            .carve_vertical_seam_with_constraint(seam, constraint)
+    }
+    fn carve_horizontal_seam(
+        &mut self,
+        seam: &HorizontalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        self.matrix
+            .carve_horizontal_seam_with_constraint(seam, constraint)
+    }
+    fn insert_vertical_seam(
+        &mut self,
+        seam: &VerticalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        self.matrix
+            .insert_vertical_seam_with_window(seam, 1, constraint, self.fill)
+    }
+    fn insert_horizontal_seam(
+        &mut self,
+        seam: &HorizontalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        self.matrix
+            .insert_horizontal_seam_with_window(seam, 1, constraint, self.fill)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Colour image plus any number of named auxiliary layers of the same size,
+/// all of which every seam is removed from or inserted into.
+pub struct LayeredImage {
+    pub color: Matrix<CustomColor>,
+    /// How inserted seams are filled in the colour image.
+    pub interpolation: Interpolation,
+    /// Shape the seams applied to the image may have, which has to allow for
+    /// the one they were found with.
+    pub constraint: SeamConstraint,
+    layers: Vec<(String, Box<dyn SeamLayer>)>,
+}
+
+impl LayeredImage {
+    pub fn new(color: Matrix<CustomColor>) -> Self {
+        LayeredImage {
+            color,
+            interpolation: Interpolation::default(),
+            constraint: SeamConstraint::default(),
+            layers: Vec::new(),
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.color.width()
+    }
+    pub fn height(&self) -> usize {
+        self.color.height()
+    }
+
+    /// Adds a layer, a `Layer<T>` or any other `SeamLayer`, failing if its
+    /// size differs from the image or the name is already taken.
+    pub fn add_layer(&mut self, name: &str, layer: impl SeamLayer + 'static) -> Result<()> {
+        if layer.width() != self.width() || layer.height() != self.height() {
+            return Err(Error::SizeMismatch {
+                expected: (self.width(), self.height()),
+                found: (layer.width(), layer.height()),
+            });
+        }
+        if self.layers.iter().any(|(layer_name, _)| layer_name == name) {
+            return Err(Error::DuplicateLayer(name.to_string()));
+        }
+        self.layers.push((name.to_string(), Box::new(layer)));
+        Ok(())
+    }
+    pub fn layer<T: 'static>(&self, name: &str) -> Option<&Layer<T>> {
+        self.seam_layer::<Layer<T>>(name)
+    }
+    /// Layer `name` if it is an `L`, for layers other than `Layer<T>`.
+    pub fn seam_layer<L: 'static>(&self, name: &str) -> Option<&L> {
+        self.layers
+            .iter()
+            .find(|(layer_name, _)| layer_name == name)
+            .and_then(|(_, layer)| layer.as_any().downcast_ref::<L>())
+    }
+
+    /// Layers always have the size of the colour image, so a seam the
+    /// colour image accepts fits every layer too.
+    pub fn carve_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        self.color
+            .carve_vertical_seam_with_constraint(seam, &self.constraint)?;
+        for (_, layer) in self.layers.iter_mut() {
+            layer.carve_vertical_seam(seam, &self.constraint)?;
+        }
+        Ok(())
+    }
+    pub fn carve_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        self.color
+            .carve_horizontal_seam_with_constraint(seam, &self.constraint)?;
+        for (_, layer) in self.layers.iter_mut() {
+            layer.carve_horizontal_seam(seam, &self.constraint)?;
+        }
+        Ok(())
+    }
+    pub fn insert_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        self.color.insert_vertical_seam_with_constraint(
+            seam,
+            self.interpolation,
+            &self.constraint,
+        )?;
+        for (_, layer) in self.layers.iter_mut() {
+            layer.insert_vertical_seam(seam, &self.constraint)?;
+        }
+        Ok(())
+    }
+    pub fn insert_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        self.color.insert_horizontal_seam_with_constraint(
+            seam,
+            self.interpolation,
+            &self.constraint,
+        )?;
+        for (_, layer) in self.layers.iter_mut() {
+            layer.insert_horizontal_seam(seam, &self.constraint)?;
+        }
+        Ok(())
+    }
+    pub fn apply(&mut self, operation: &SeamOperation) -> Result<()> {
+        match operation {
+            SeamOperation::CarveVertical(seam) => self.carve_vertical_seam(seam),
+            SeamOperation::InsertVertical(seam) => self.insert_vertical_seam(seam),
+            SeamOperation::CarveHorizontal(seam) => self.carve_horizontal_seam(seam),
+            SeamOperation::InsertHorizontal(seam) => self.insert_horizontal_seam(seam),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::structs::pixel::Pixel;
+
+#[cfg(test)]
+#[path = "../tests/linear.rs"]
+mod linear_tests;
+
+/// sRGB transfer function, from linear light to the perceptual encoding.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        12.92 * value
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Colour in linear light at full `f32` precision, for 16-bit and HDR
+/// images. Channels are not limited to `0.0..=1.0`, so highlights brighter
+/// than white survive carving and insertion.
+#[derive(Clone, Copy, Debug)]
+pub struct LinearColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub is_inserted: bool,
+}
+
+impl PartialEq for LinearColor {
+    fn eq(&self, other: &Self) -> bool {
+        self.r == other.r && self.g == other.g && self.b == other.b
+    }
+}
+
+impl Pixel for LinearColor {
+    /// Luminance in the sRGB encoding, so energies match those of 8-bit
+    /// images. Above white it continues logarithmically with the same slope,
+    /// which keeps detail in highlights without letting them dominate.
+    fn luma(&self) -> f32 {
+        let luminance = (0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b).max(0.0);
+        if luminance <= 1.0 {
+            linear_to_srgb(luminance)
+        } else {
+            1.0 + 1.055 / 2.4 * luminance.ln()
+        }
+    }
+    /// Mean in linear light, which is how light actually mixes.
+    fn average(pixels: &[Self]) -> Self {
+        let sum = pixels.iter().fold((0.0, 0.0, 0.0), |acc, value| {
+            (acc.0 + value.r, acc.1 + value.g, acc.2 + value.b)
+        });
+        let count = pixels.len() as f32;
+        LinearColor {
+            r: sum.0 / count,
+            g: sum.1 / count,
+            b: sum.2 / count,
+            is_inserted: false,
+        }
+    }
+    fn blend(pixels: &[Self], weights: &[f32]) -> Self {
+        let sum =
+            pixels
+                .iter()
+                .zip(weights.iter())
+                .fold((0.0, 0.0, 0.0), |acc, (value, weight)| {
+                    (
+                        acc.0 + value.r * weight,
+                        acc.1 + value.g * weight,
+                        acc.2 + value.b * weight,
+                    )
+                });
+        LinearColor {
+            r: sum.0.max(0.0),
+            g: sum.1.max(0.0),
+            b: sum.2.max(0.0),
+            is_inserted: false,
+        }
+    }
+    fn mark_inserted(&mut self) {
+        self.is_inserted = true;
+    }
+    fn is_inserted(&self) -> bool {
+        self.is_inserted
+    }
+}
@@ -1,21 +1,180 @@
-use std::f32::INFINITY;
-
-use crate::{structs::color::CustomColor, utils::GradientMagnitudePoint, *};
+use crate::{
+    error::{Error, Result},
+    structs::{interpolation::Interpolation, pixel::Pixel, window_size::WindowSize},
+    utils::GradientMagnitudePoint,
+};
 use ::rand::{rngs::ThreadRng, Rng};
+use serde::{Deserialize, Serialize};
 
 #[cfg(test)]
+#[allow(
+    clippy::inherent_to_string,
+    clippy::needless_return,
+    clippy::redundant_field_names,
+    clippy::single_char_add_str,
+    clippy::wrong_self_convention
+)]
 #[path = "../tests/matrix.rs"]
 mod matrix_tests;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct HorizontalSeam {
     pub rows: Vec<usize>,
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct VerticalSeam {
     pub columns: Vec<usize>,
 }
 
+/// Positions across a seam of `to_length` pixels following `path`, a seam
+/// of `from_across` wide pixels, scaled to `to_across`. Positions are
+/// interpolated between the centres of the original pixels and then limited
+/// to one pixel of movement per step, so the result is always connected.
+fn upscale_path(
+    path: &[usize],
+    from_across: usize,
+    to_across: usize,
+    to_length: usize,
+) -> Vec<usize> {
+    let scale_across = to_across as f32 / from_across as f32;
+    let scale_along = to_length as f32 / path.len() as f32;
+    let last = path.len() - 1;
+
+    let mut result: Vec<usize> = Vec::with_capacity(to_length);
+    for position in 0..to_length {
+        let along = (position as f32 + 0.5) / scale_along - 0.5;
+        let before = along.floor().clamp(0.0, last as f32) as usize;
+        let after = (before + 1).min(last);
+        let t = (along - before as f32).clamp(0.0, 1.0);
+        let across = path[before] as f32 * (1.0 - t) + path[after] as f32 * t;
+
+        let mut across = (((across + 0.5) * scale_across) as usize).min(to_across - 1);
+        if let Some(previous) = result.last() {
+            across = across.clamp(previous.saturating_sub(1), previous + 1);
+        }
+        result.push(across);
+    }
+    result
+}
+
+/// Checks `path`, a seam with a pixel for each of the `length` rows (or
+/// columns) of an image `across` pixels wide (or high), against the image
+/// and `constraint`.
+pub(crate) fn validate_path(
+    path: &[usize],
+    length: usize,
+    across: usize,
+    constraint: &SeamConstraint,
+) -> Result<()> {
+    if length == 0 || across == 0 {
+        return Err(Error::DegenerateImage {
+            width: across,
+            height: length,
+        });
+    }
+    if path.len() != length {
+        return Err(Error::SeamLength {
+            expected: length,
+            found: path.len(),
+        });
+    }
+    if let Some((index, position)) = path
+        .iter()
+        .enumerate()
+        .find(|(_, position)| **position >= across)
+    {
+        return Err(Error::SeamOutOfBounds {
+            index,
+            position: *position,
+            limit: across,
+        });
+    }
+    if let Some(index) =
+        (1..length).find(|index| path[index - 1].abs_diff(path[*index]) > constraint.max_step)
+    {
+        return Err(Error::DisconnectedSeam {
+            index,
+            step: path[index - 1].abs_diff(path[index]),
+        });
+    }
+    Ok(())
+}
+
+impl VerticalSeam {
+    /// Checks that the seam has a column for every row of `matrix`, all of
+    /// them inside it, and that it is 8-connected.
+    pub fn validate<T>(&self, matrix: &Matrix<T>) -> Result<()>
+    where
+        T: Clone + std::marker::Send + Sync + Copy,
+    {
+        self.validate_with_constraint(matrix, &SeamConstraint::default())
+    }
+    /// Like `validate`, with steps of up to `constraint.max_step` columns
+    /// between rows.
+    pub fn validate_with_constraint<T>(
+        &self,
+        matrix: &Matrix<T>,
+        constraint: &SeamConstraint,
+    ) -> Result<()>
+    where
+        T: Clone + std::marker::Send + Sync + Copy,
+    {
+        validate_path(&self.columns, matrix.height(), matrix.width(), constraint)
+    }
+    /// The seam of an image of size `from` carried over to the same image at
+    /// size `to`, e.g. from a downscaled proxy to the full resolution master.
+    pub fn upscale(&self, from: &WindowSize, to: &WindowSize) -> VerticalSeam {
+        VerticalSeam {
+            columns: upscale_path(&self.columns, from.width, to.width, to.height),
+        }
+    }
+}
+
+impl HorizontalSeam {
+    /// Horizontal counterpart of `VerticalSeam::validate`.
+    pub fn validate<T>(&self, matrix: &Matrix<T>) -> Result<()>
+    where
+        T: Clone + std::marker::Send + Sync + Copy,
+    {
+        self.validate_with_constraint(matrix, &SeamConstraint::default())
+    }
+    pub fn validate_with_constraint<T>(
+        &self,
+        matrix: &Matrix<T>,
+        constraint: &SeamConstraint,
+    ) -> Result<()>
+    where
+        T: Clone + std::marker::Send + Sync + Copy,
+    {
+        validate_path(&self.rows, matrix.width(), matrix.height(), constraint)
+    }
+    /// Horizontal counterpart of `VerticalSeam::upscale`.
+    pub fn upscale(&self, from: &WindowSize, to: &WindowSize) -> HorizontalSeam {
+        HorizontalSeam {
+            rows: upscale_path(&self.rows, from.height, to.height, to.width),
+        }
+    }
+}
+
+/// Limits on the shape of a seam.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SeamConstraint {
+    /// Largest distance a seam may move between neighbouring rows (or
+    /// columns), 0 for straight seams.
+    pub max_step: usize,
+    /// Extra cost per pixel of every step, so seams prefer to stay straight.
+    pub slope_penalty: f32,
+}
+
+impl Default for SeamConstraint {
+    fn default() -> Self {
+        SeamConstraint {
+            max_step: 1,
+            slope_penalty: 0.0,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Matrix<T> {
     width: usize,
@@ -29,17 +188,39 @@ where
         self.width
     }
     pub fn height(&self) -> usize {
-        self.vector.len() / self.width
+        // a matrix without columns has no rows either
+        self.vector.len().checked_div(self.width).unwrap_or(0)
     }
     pub fn new(vector: Vec<T>, width: usize) -> Self {
-        Matrix {
-            width: width,
-            vector: vector,
-        }
+        Matrix { width, vector }
+    }
+    pub fn transpose(&self) -> Matrix<T> {
+        let height = self.height();
+        Matrix::new(
+            (0..self.width)
+                .flat_map(|column| (0..height).map(move |row| (row, column)))
+                .map(|(row, column)| self.vector[row * self.width + column])
+                .collect(),
+            height,
+        )
     }
-    pub fn carve_horizontal_seam(&mut self, seam: &HorizontalSeam) {
+    /// Fails without touching the image if `seam.validate` rejects the seam,
+    /// or if the image is a single row, which would leave nothing behind.
+    pub fn carve_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        self.carve_horizontal_seam_with_constraint(seam, &SeamConstraint::default())
+    }
+    /// Like `carve_horizontal_seam`, for seams found with a looser
+    /// `constraint`.
+    pub fn carve_horizontal_seam_with_constraint(
+        &mut self,
+        seam: &HorizontalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        seam.validate_with_constraint(self, constraint)?;
+        if self.height() < 2 {
+            return Err(self.degenerate());
+        }
         let column_vectors: Vec<Vec<T>> = (0..self.width)
-            .into_iter()
             .map(|column| {
                 let mut vector_result: Vec<T> = self
                     .vector
@@ -51,12 +232,11 @@ where
                 let row = seam.rows[column];
                 vector_result.remove(row);
 
-                return vector_result;
+                vector_result
             })
             .collect::<Vec<Vec<T>>>();
 
         let result = (0..(self.height() - 1))
-            .into_iter()
             .map(|row| {
                 column_vectors
                     .iter()
@@ -67,8 +247,21 @@ where
             .concat();
 
         self.vector = result;
+        Ok(())
+    }
+    /// Vertical counterpart of `carve_horizontal_seam`.
+    pub fn carve_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        self.carve_vertical_seam_with_constraint(seam, &SeamConstraint::default())
     }
-    pub fn carve_vertical_seam(&mut self, seam: &VerticalSeam) {
+    pub fn carve_vertical_seam_with_constraint(
+        &mut self,
+        seam: &VerticalSeam,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        seam.validate_with_constraint(self, constraint)?;
+        if self.width < 2 {
+            return Err(self.degenerate());
+        }
         let resulting_vector: Vec<T> = (0..self.height())
             .map(|row| {
                 let mut row_vector = self
@@ -80,228 +273,49 @@ where
                     .collect::<Vec<T>>();
                 let column = seam.columns[row];
                 row_vector.remove(column);
-                return row_vector;
+                row_vector
             })
             .collect::<Vec<Vec<T>>>()
             .concat();
 
         self.vector = resulting_vector;
-        self.width = self.width - 1;
-    }
-}
-
-impl Matrix<GradientMagnitudePoint> {
-    pub fn extract_vertical_seam(
-        &self,
-        rng: &mut ThreadRng,
-        avoid_inserted: bool,
-    ) -> (VerticalSeam, f32) {
-        let mut dp_result = self
-            .vector
-            .iter()
-            .map(|point| {
-                if point.is_inserted {
-                    if avoid_inserted {
-                        INFINITY
-                    } else {
-                        0.0
-                    }
-                } else {
-                    point.value
-                }
-            })
-            .collect::<Vec<f32>>();
-        let width = self.width;
-        let height = self.height();
-
-        // fill in the vector using dynamic programming
-        for i in 1..height {
-            for j in 0..width {
-                dp_result[i * width + j] = dp_result[(i - 1) * width + j]
-                    .min(if j == 0 {
-                        dp_result[(i - 1) * width + j]
-                    } else {
-                        dp_result[(i - 1) * width + (j - 1)]
-                    })
-                    .min(if j == width - 1 {
-                        dp_result[(i - 1) * width + j]
-                    } else {
-                        dp_result[(i - 1) * width + (j + 1)]
-                    })
-                    + dp_result[i * width + j];
-            }
-        }
-
-        let mut columns = vec![0; height];
-        let mut total_energy = 0.0;
-
-        // calculate the last element in seam by randomly
-        // selecting one of the minimum points in the last row
-        let mut min_columns = Vec::with_capacity(width);
-        let mut current_min = dp_result[(height - 1) * width];
-        dp_result
-            .iter()
-            .skip((height - 1) * width)
-            .enumerate()
-            .for_each(|(column, value)| {
-                if *value < current_min {
-                    min_columns.truncate(0);
-                    min_columns.push(column);
-                    current_min = *value;
-                } else if *value == current_min {
-                    min_columns.push(column);
-                }
-            });
-        let last_column = min_columns[rng.gen_range(0..min_columns.len())];
-        columns[height - 1] = last_column;
-        total_energy =
-            total_energy + self.vector[self.vector.len() - self.width + last_column].value;
-
-        // calculate the rest of the indexes for the seam
-        for row in (0..height - 1).rev() {
-            let column = {
-                let mid_column = columns[row + 1];
-                let left_column = if mid_column == 0 {
-                    mid_column
-                } else {
-                    mid_column - 1
-                };
-
-                let mut min_column = if dp_result[row * self.width + left_column]
-                    < dp_result[row * self.width + mid_column]
-                {
-                    left_column
-                } else {
-                    mid_column
-                };
-
-                let right_column = if mid_column == width - 1 {
-                    mid_column
-                } else {
-                    mid_column + 1
-                };
-
-                min_column = if dp_result[row * self.width + min_column]
-                    < dp_result[row * self.width + right_column]
-                {
-                    min_column
-                } else {
-                    right_column
-                };
-                min_column
-            };
-            columns[row] = column;
-            total_energy = total_energy + self.vector[self.width * row + column].value;
-        }
-
-        (VerticalSeam { columns: columns }, total_energy)
+        self.width -= 1;
+        Ok(())
     }
-    pub fn extract_horizontal_seam(
-        &self,
-        rng: &mut ThreadRng,
-        avoid_inserted: bool,
-    ) -> (HorizontalSeam, f32) {
-        let mut dp_result = self
-            .vector
-            .iter()
-            .map(|point| {
-                if point.is_inserted {
-                    if avoid_inserted {
-                        INFINITY
-                    } else {
-                        0.0
-                    }
-                } else {
-                    point.value
-                }
-            })
-            .collect::<Vec<f32>>();
-        let width = self.width;
-        let height = self.vector.len() / width;
 
-        // fill in the vector using dynamic programming
-        for j in 1..width {
-            for i in 0..height {
-                dp_result[i * width + j] = dp_result[i * width + j - 1]
-                    .min(if i == 0 {
-                        dp_result[i * width + j - 1]
-                    } else {
-                        dp_result[(i - 1) * width + j - 1]
-                    })
-                    .min(if i == height - 1 {
-                        dp_result[i * width + j - 1]
-                    } else {
-                        dp_result[(i + 1) * width + j - 1]
-                    })
-                    + dp_result[i * width + j];
-            }
+    fn degenerate(&self) -> Error {
+        Error::DegenerateImage {
+            width: self.width,
+            height: self.height(),
         }
-
-        let mut rows = vec![0; width];
-        let mut total_energy = 0.0;
-
-        // calculate the last element in seam by randomly
-        // selecting one of the minimum points in the last column
-        let mut min_rows = Vec::with_capacity(height);
-        let mut current_min = dp_result[width - 1];
-        dp_result
-            .iter()
-            .skip(width - 1)
-            .step_by(width)
-            .enumerate()
-            .for_each(|(row, value)| {
-                if *value < current_min {
-                    min_rows.truncate(0);
-                    min_rows.push(row);
-                    current_min = *value;
-                } else if *value == current_min {
-                    min_rows.push(row);
-                }
-            });
-        let last_row = min_rows[rng.gen_range(0..min_rows.len())];
-        rows[width - 1] = last_row;
-        total_energy = total_energy + self.vector[self.width * (last_row + 1) - 1].value;
-
-        // calculate the rest of the indexes for the seam
-        for column in (0..width - 1).rev() {
-            let row = {
-                let mid_row = rows[column + 1];
-                let top_row = if mid_row > 0 { mid_row - 1 } else { mid_row };
-
-                let mut min_row = if dp_result[self.width * top_row + column]
-                    < dp_result[self.width * mid_row + column]
-                {
-                    top_row
-                } else {
-                    mid_row
-                };
-
-                let bottom_row = if mid_row >= height - 1 {
-                    mid_row
-                } else {
-                    mid_row + 1
-                };
-
-                min_row = if dp_result[self.width * min_row + column]
-                    < dp_result[self.width * bottom_row + column]
-                {
-                    min_row
-                } else {
-                    bottom_row
-                };
-
-                min_row
-            };
-            rows[column] = row;
-            total_energy = total_energy + self.vector[self.width * row + column].value;
-        }
-
-        (HorizontalSeam { rows: rows }, total_energy)
     }
 }
 
-impl Matrix<CustomColor> {
-    pub fn insert_vertical_seam(&mut self, seam: &VerticalSeam) {
+impl<T> Matrix<T>
+where
+    T: Clone + std::marker::Send + Sync + Copy,
+{
+    /// Inserts a pixel after every seam pixel, computed by `fill` from the
+    /// seam pixel and its direct neighbours in the row, together with the
+    /// position of the seam pixel among them.
+    pub fn insert_vertical_seam_with(
+        &mut self,
+        seam: &VerticalSeam,
+        fill: impl Fn(&[T], usize) -> T,
+    ) -> Result<()> {
+        self.insert_vertical_seam_with_window(seam, 1, &SeamConstraint::default(), fill)
+    }
+    /// Like `insert_vertical_seam_with`, with up to `radius` neighbours on
+    /// either side of the seam pixel handed to `fill` and seams validated
+    /// against `constraint`.
+    pub fn insert_vertical_seam_with_window(
+        &mut self,
+        seam: &VerticalSeam,
+        radius: usize,
+        constraint: &SeamConstraint,
+        fill: impl Fn(&[T], usize) -> T,
+    ) -> Result<()> {
+        seam.validate_with_constraint(self, constraint)?;
         let columns = &seam.columns;
 
         let resulting_vector = columns
@@ -309,47 +323,47 @@ impl Matrix<CustomColor> {
             .cloned()
             .enumerate()
             .map(|(row, column)| {
-                let avg = {
-                    let fold = self
-                        .vector
-                        .iter()
-                        .skip(self.width * row + (if column > 0 { column - 1 } else { column }))
-                        .take((self.width - column).min(if column > 0 { 3 } else { 2 }))
-                        .fold((0, (0.0, 0.0, 0.0)), |acc, value| {
-                            (
-                                acc.0 + 1,
-                                (acc.1 .0 + value.r, acc.1 .1 + value.g, acc.1 .2 + value.b),
-                            )
-                        });
-                    CustomColor {
-                        r: fold.1 .0 / (fold.0 as f32),
-                        g: fold.1 .1 / (fold.0 as f32),
-                        b: fold.1 .2 / (fold.0 as f32),
-                        is_inserted: true,
-                    }
-                };
-
                 let mut row_vector = self
                     .vector
                     .iter()
                     .cloned()
                     .skip(self.width * row)
                     .take(self.width)
-                    .collect::<Vec<CustomColor>>();
-                row_vector.insert(column + 1, avg);
-                row_vector[column].is_inserted = true;
-                if column < row_vector.len() - 2 {
-                    row_vector[column + 2].is_inserted = true;
-                }
-                return row_vector;
+                    .collect::<Vec<T>>();
+
+                let start = column.saturating_sub(radius);
+                let inserted = fill(
+                    &row_vector[start..(column + radius + 1).min(self.width)],
+                    column - start,
+                );
+
+                row_vector.insert(column + 1, inserted);
+                row_vector
             })
-            .collect::<Vec<Vec<CustomColor>>>()
+            .collect::<Vec<Vec<T>>>()
             .concat();
 
         self.vector = resulting_vector;
-        self.width = self.width + 1;
+        self.width += 1;
+        Ok(())
+    }
+    /// Horizontal counterpart of `insert_vertical_seam_with`, with the
+    /// neighbours taken from the column.
+    pub fn insert_horizontal_seam_with(
+        &mut self,
+        seam: &HorizontalSeam,
+        fill: impl Fn(&[T], usize) -> T,
+    ) -> Result<()> {
+        self.insert_horizontal_seam_with_window(seam, 1, &SeamConstraint::default(), fill)
     }
-    pub fn insert_horizontal_seam(&mut self, seam: &HorizontalSeam) {
+    pub fn insert_horizontal_seam_with_window(
+        &mut self,
+        seam: &HorizontalSeam,
+        radius: usize,
+        constraint: &SeamConstraint,
+        fill: impl Fn(&[T], usize) -> T,
+    ) -> Result<()> {
+        seam.validate_with_constraint(self, constraint)?;
         let height = self.height();
 
         let rows = &seam.rows;
@@ -359,54 +373,317 @@ impl Matrix<CustomColor> {
             .cloned()
             .enumerate()
             .map(|(column, row)| {
-                let avg = {
-                    let starting_row = if row > 0 { row - 1 } else { row };
-                    let fold = self
-                        .vector
-                        .iter()
-                        .skip(self.width * starting_row + column)
-                        .step_by(self.width)
-                        .take((height - starting_row).min(if row > 0 { 3 } else { 2 }))
-                        .fold((0, (0.0, 0.0, 0.0)), |acc, value| {
-                            (
-                                acc.0 + 1,
-                                (acc.1 .0 + value.r, acc.1 .1 + value.g, acc.1 .2 + value.b),
-                            )
-                        });
-                    CustomColor {
-                        r: fold.1 .0 / (fold.0 as f32),
-                        g: fold.1 .1 / (fold.0 as f32),
-                        b: fold.1 .2 / (fold.0 as f32),
-                        is_inserted: true,
-                    }
-                };
-
                 let mut column_vector = self
                     .vector
                     .iter()
                     .cloned()
                     .skip(column)
                     .step_by(self.width)
-                    .collect::<Vec<CustomColor>>();
-                column_vector.insert(row + 1, avg);
-                column_vector[row].is_inserted = true;
-                if row < column_vector.len() - 2 {
-                    column_vector[row + 2].is_inserted = true;
-                }
-                return column_vector;
+                    .collect::<Vec<T>>();
+
+                let start = row.saturating_sub(radius);
+                let inserted = fill(
+                    &column_vector[start..(row + radius + 1).min(height)],
+                    row - start,
+                );
+
+                column_vector.insert(row + 1, inserted);
+                column_vector
             })
-            .collect::<Vec<Vec<CustomColor>>>();
+            .collect::<Vec<Vec<T>>>();
 
         let result = (0..(height + 1))
-            .into_iter()
             .map(|row| {
                 column_vectors
                     .iter()
                     .map(|column_vector| column_vector[row])
-                    .collect::<Vec<CustomColor>>()
+                    .collect::<Vec<T>>()
             })
-            .collect::<Vec<Vec<CustomColor>>>()
+            .collect::<Vec<Vec<T>>>()
             .concat();
         self.vector = result;
+        Ok(())
+    }
+}
+
+impl Matrix<GradientMagnitudePoint> {
+    /// Per-pixel cost of passing a seam through, with pixels of earlier
+    /// inserted seams either avoided or preferred.
+    pub fn seam_costs(&self, avoid_inserted: bool) -> Matrix<f32> {
+        Matrix::new(
+            self.vector
+                .iter()
+                .map(|point| {
+                    if point.is_inserted {
+                        if avoid_inserted {
+                            f32::INFINITY
+                        } else {
+                            0.0
+                        }
+                    } else {
+                        point.value
+                    }
+                })
+                .collect::<Vec<f32>>(),
+            self.width,
+        )
+    }
+    pub fn vertical_seam_energy(&self, seam: &VerticalSeam) -> f32 {
+        seam.columns
+            .iter()
+            .enumerate()
+            .map(|(row, column)| self.vector[self.width * row + column].value)
+            .sum()
+    }
+    pub fn horizontal_seam_energy(&self, seam: &HorizontalSeam) -> f32 {
+        seam.rows
+            .iter()
+            .enumerate()
+            .map(|(column, row)| self.vector[self.width * row + column].value)
+            .sum()
+    }
+    pub fn extract_vertical_seam(
+        &self,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(VerticalSeam, f32)> {
+        self.extract_vertical_seam_with_constraint(rng, avoid_inserted, &SeamConstraint::default())
+    }
+    pub fn extract_horizontal_seam(
+        &self,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+    ) -> Result<(HorizontalSeam, f32)> {
+        self.extract_horizontal_seam_with_constraint(
+            rng,
+            avoid_inserted,
+            &SeamConstraint::default(),
+        )
+    }
+    pub fn extract_vertical_seam_with_constraint(
+        &self,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+        constraint: &SeamConstraint,
+    ) -> Result<(VerticalSeam, f32)> {
+        let seam = self
+            .seam_costs(avoid_inserted)
+            .min_vertical_seam_with_constraint(rng, constraint)?;
+        let total_energy = self.vertical_seam_energy(&seam);
+
+        Ok((seam, total_energy))
+    }
+    pub fn extract_horizontal_seam_with_constraint(
+        &self,
+        rng: &mut ThreadRng,
+        avoid_inserted: bool,
+        constraint: &SeamConstraint,
+    ) -> Result<(HorizontalSeam, f32)> {
+        let seam = self
+            .seam_costs(avoid_inserted)
+            .min_horizontal_seam_with_constraint(rng, constraint)?;
+        let total_energy = self.horizontal_seam_energy(&seam);
+
+        Ok((seam, total_energy))
+    }
+}
+
+/// Seam search over a matrix of per-pixel costs, shared by every energy
+/// representation.
+impl Matrix<f32> {
+    pub fn min_vertical_seam(&self, rng: &mut ThreadRng) -> Result<VerticalSeam> {
+        self.min_vertical_seam_with_constraint(rng, &SeamConstraint::default())
+    }
+    pub fn min_horizontal_seam(&self, rng: &mut ThreadRng) -> Result<HorizontalSeam> {
+        self.min_horizontal_seam_with_constraint(rng, &SeamConstraint::default())
+    }
+    /// Fails on an empty matrix, which has no seams.
+    pub fn min_vertical_seam_with_constraint(
+        &self,
+        rng: &mut ThreadRng,
+        constraint: &SeamConstraint,
+    ) -> Result<VerticalSeam> {
+        if self.vector.is_empty() {
+            return Err(self.degenerate());
+        }
+        let width = self.width;
+        Ok(VerticalSeam {
+            columns: self.min_seam(
+                self.height(),
+                width,
+                |row, column| row * width + column,
+                rng,
+                constraint,
+            ),
+        })
+    }
+    /// Horizontal counterpart of `min_vertical_seam_with_constraint`, running
+    /// the same search column by column.
+    pub fn min_horizontal_seam_with_constraint(
+        &self,
+        rng: &mut ThreadRng,
+        constraint: &SeamConstraint,
+    ) -> Result<HorizontalSeam> {
+        if self.vector.is_empty() {
+            return Err(self.degenerate());
+        }
+        let width = self.width;
+        Ok(HorizontalSeam {
+            rows: self.min_seam(
+                width,
+                self.height(),
+                |column, row| row * width + column,
+                rng,
+                constraint,
+            ),
+        })
+    }
+
+    /// Cheapest seam through `length` lines of `across` costs each, with the
+    /// cost at position `position` of line `line` stored at
+    /// `index(line, position)`. Returns the position of the seam in every
+    /// line.
+    fn min_seam(
+        &self,
+        length: usize,
+        across: usize,
+        index: impl Fn(usize, usize) -> usize,
+        rng: &mut ThreadRng,
+        constraint: &SeamConstraint,
+    ) -> Vec<usize> {
+        let mut dp_result = self.vector.clone();
+
+        // positions of the previous line reachable from `position`, with the
+        // slope penalty of stepping there
+        let steps = |position: usize| {
+            (position.saturating_sub(constraint.max_step)
+                ..=(position + constraint.max_step).min(across - 1))
+                .map(move |previous| {
+                    (
+                        previous,
+                        constraint.slope_penalty * previous.abs_diff(position) as f32,
+                    )
+                })
+        };
+        // the cheapest of them, taking the last one on ties
+        let best_step = |dp_result: &[f32], line: usize, position: usize| {
+            steps(position)
+                .map(|(previous, penalty)| (previous, dp_result[index(line, previous)] + penalty))
+                .fold((position, f32::INFINITY), |best, candidate| {
+                    if candidate.1 <= best.1 {
+                        candidate
+                    } else {
+                        best
+                    }
+                })
+        };
+
+        // fill in the vector using dynamic programming
+        for line in 1..length {
+            for position in 0..across {
+                dp_result[index(line, position)] += best_step(&dp_result, line - 1, position).1;
+            }
+        }
+
+        let mut positions = vec![0; length];
+
+        // calculate the last element in seam by randomly
+        // selecting one of the minimum points in the last line
+        let mut min_positions = Vec::with_capacity(across);
+        let mut current_min = dp_result[index(length - 1, 0)];
+        for position in 0..across {
+            let value = dp_result[index(length - 1, position)];
+            if value < current_min {
+                min_positions.truncate(0);
+                min_positions.push(position);
+                current_min = value;
+            } else if value == current_min {
+                min_positions.push(position);
+            }
+        }
+        positions[length - 1] = min_positions[rng.gen_range(0..min_positions.len())];
+
+        // calculate the rest of the indexes for the seam
+        for line in (0..length - 1).rev() {
+            positions[line] = best_step(&dp_result, line, positions[line + 1]).0;
+        }
+
+        positions
+    }
+}
+
+impl<T: Pixel> Matrix<T> {
+    pub fn insert_vertical_seam(&mut self, seam: &VerticalSeam) -> Result<()> {
+        self.insert_vertical_seam_interpolated(seam, Interpolation::Average)
+    }
+    pub fn insert_horizontal_seam(&mut self, seam: &HorizontalSeam) -> Result<()> {
+        self.insert_horizontal_seam_interpolated(seam, Interpolation::Average)
+    }
+    pub fn insert_vertical_seam_interpolated(
+        &mut self,
+        seam: &VerticalSeam,
+        interpolation: Interpolation,
+    ) -> Result<()> {
+        self.insert_vertical_seam_with_constraint(seam, interpolation, &SeamConstraint::default())
+    }
+    /// Like `insert_vertical_seam_interpolated`, for seams found with a
+    /// looser `constraint`.
+    pub fn insert_vertical_seam_with_constraint(
+        &mut self,
+        seam: &VerticalSeam,
+        interpolation: Interpolation,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        self.insert_vertical_seam_with_window(
+            seam,
+            Interpolation::RADIUS,
+            constraint,
+            |neighbours, index| {
+                let mut inserted = interpolation.fill(neighbours, index);
+                inserted.mark_inserted();
+                inserted
+            },
+        )?;
+
+        for (row, column) in seam.columns.iter().cloned().enumerate() {
+            self.vector[self.width * row + column].mark_inserted();
+            if column < self.width - 2 {
+                self.vector[self.width * row + column + 2].mark_inserted();
+            }
+        }
+        Ok(())
+    }
+    pub fn insert_horizontal_seam_interpolated(
+        &mut self,
+        seam: &HorizontalSeam,
+        interpolation: Interpolation,
+    ) -> Result<()> {
+        self.insert_horizontal_seam_with_constraint(seam, interpolation, &SeamConstraint::default())
+    }
+    pub fn insert_horizontal_seam_with_constraint(
+        &mut self,
+        seam: &HorizontalSeam,
+        interpolation: Interpolation,
+        constraint: &SeamConstraint,
+    ) -> Result<()> {
+        self.insert_horizontal_seam_with_window(
+            seam,
+            Interpolation::RADIUS,
+            constraint,
+            |neighbours, index| {
+                let mut inserted = interpolation.fill(neighbours, index);
+                inserted.mark_inserted();
+                inserted
+            },
+        )?;
+
+        let height = self.height();
+        for (column, row) in seam.rows.iter().cloned().enumerate() {
+            self.vector[self.width * row + column].mark_inserted();
+            if row < height - 2 {
+                self.vector[self.width * (row + 2) + column].mark_inserted();
+            }
+        }
+        Ok(())
     }
 }
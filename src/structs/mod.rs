@@ -1,3 +1,9 @@
 pub mod color;
+pub mod compact;
+pub mod interpolation;
+pub mod layers;
+pub mod linear;
 pub mod matrix;
+pub mod pixel;
+pub mod target_size;
 pub mod window_size;
@@ -0,0 +1,63 @@
+/// Operations the energy, carving and insertion routines need from a pixel
+/// type, so the same code can run on full precision and compact storage.
+pub trait Pixel: Clone + Send + Sync + Copy {
+    /// Perceived brightness of the pixel in the `0.0..=1.0` range.
+    fn luma(&self) -> f32;
+
+    /// Plain mean of the given neighbours, used to fill inserted seam pixels.
+    fn average(pixels: &[Self]) -> Self;
+
+    /// Sum of the pixels scaled by `weights`, which may be negative. Types
+    /// with a bounded range clamp the result to it.
+    fn blend(pixels: &[Self], weights: &[f32]) -> Self;
+
+    /// Pixel types that keep the inserted flag out of line (see
+    /// `CompactImage`) leave this as a no-op.
+    fn mark_inserted(&mut self) {}
+
+    fn is_inserted(&self) -> bool {
+        false
+    }
+}
+
+/// Single channel values such as depth maps.
+impl Pixel for f32 {
+    fn luma(&self) -> f32 {
+        *self
+    }
+    fn average(pixels: &[Self]) -> Self {
+        pixels.iter().sum::<f32>() / pixels.len() as f32
+    }
+    fn blend(pixels: &[Self], weights: &[f32]) -> Self {
+        pixels
+            .iter()
+            .zip(weights.iter())
+            .map(|(pixel, weight)| pixel * weight)
+            .sum()
+    }
+}
+
+/// Multi channel values such as normals or UV coordinates.
+impl<const N: usize> Pixel for [f32; N] {
+    fn luma(&self) -> f32 {
+        self.iter().sum::<f32>() / N as f32
+    }
+    fn average(pixels: &[Self]) -> Self {
+        let mut result = [0.0; N];
+        for pixel in pixels {
+            for (channel, value) in result.iter_mut().zip(pixel.iter()) {
+                *channel += value / pixels.len() as f32;
+            }
+        }
+        result
+    }
+    fn blend(pixels: &[Self], weights: &[f32]) -> Self {
+        let mut result = [0.0; N];
+        for (pixel, weight) in pixels.iter().zip(weights.iter()) {
+            for (channel, value) in result.iter_mut().zip(pixel.iter()) {
+                *channel += value * weight;
+            }
+        }
+        result
+    }
+}
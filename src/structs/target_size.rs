@@ -0,0 +1,95 @@
+use crate::structs::window_size::WindowSize;
+
+#[cfg(test)]
+#[path = "../tests/target_size.rs"]
+mod target_size_tests;
+
+/// One side of a requested output size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dimension {
+    /// Left as it is in the source image.
+    Keep,
+    Pixels(usize),
+    /// Percentage of the source image side.
+    Percent(f32),
+    /// Scaled by the same factor as the other side.
+    Proportional,
+}
+
+/// What the resize loop aims for, relative to the source image.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TargetSize {
+    /// The size of the window, following it as it is resized.
+    #[default]
+    Window,
+    Size {
+        width: Dimension,
+        height: Dimension,
+    },
+    /// Aspect ratio reached by carving only the side that is too long.
+    Aspect {
+        width: usize,
+        height: usize,
+    },
+}
+
+/// Target size and whether to only ever carve the sides that are larger than
+/// it, leaving sides that already fit untouched instead of inserting seams.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Target {
+    pub size: TargetSize,
+    pub fit: bool,
+}
+
+impl Target {
+    /// Size to resize an image of size `image` to while the window has size
+    /// `window`. Both sides are at least one pixel.
+    pub fn resolve(&self, image: &WindowSize, window: &WindowSize) -> WindowSize {
+        let scale = |side: Dimension, original: usize| match side {
+            Dimension::Keep | Dimension::Proportional => None,
+            Dimension::Pixels(pixels) => Some(pixels as f32 / original as f32),
+            Dimension::Percent(percent) => Some(percent / 100.0),
+        };
+        let (width, height) = match self.size {
+            TargetSize::Window => (window.width, window.height),
+            TargetSize::Size { width, height } => {
+                let width_scale = scale(width, image.width);
+                let height_scale = scale(height, image.height);
+                let resolve = |side: Dimension, own: Option<f32>, other: Option<f32>, original| {
+                    let factor = match side {
+                        Dimension::Proportional => other.unwrap_or(1.0),
+                        _ => own.unwrap_or(1.0),
+                    };
+                    (original as f32 * factor).round() as usize
+                };
+                (
+                    resolve(width, width_scale, height_scale, image.width),
+                    resolve(height, height_scale, width_scale, image.height),
+                )
+            }
+            TargetSize::Aspect { width, height } => {
+                if image.width * height > image.height * width {
+                    (
+                        (image.height as f32 * width as f32 / height as f32).round() as usize,
+                        image.height,
+                    )
+                } else {
+                    (
+                        image.width,
+                        (image.width as f32 * height as f32 / width as f32).round() as usize,
+                    )
+                }
+            }
+        };
+
+        let (width, height) = if self.fit {
+            (width.min(image.width), height.min(image.height))
+        } else {
+            (width, height)
+        };
+        WindowSize {
+            width: width.max(1),
+            height: height.max(1),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    energy::Energy,
+    error::Result,
+    structs::{
+        color::CustomColor,
+        matrix::{Matrix, VerticalSeam},
+    },
+    utils::GradientMagnitudePoint,
+};
+use ::rand::rngs::ThreadRng;
+
+#[cfg(test)]
+#[path = "tests/symmetry.rs"]
+mod symmetry_tests;
+
+/// Vertical mirror axis, stored as the sum of the two columns of every
+/// mirrored pair so axes between two columns stay exact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SymmetryAxis {
+    column_sum: usize,
+}
+
+impl SymmetryAxis {
+    /// Axis through the middle of a matrix of `width`, or through column 0
+    /// of an empty one, which has no columns to mirror.
+    pub fn centered(width: usize) -> Self {
+        SymmetryAxis {
+            column_sum: width.saturating_sub(1),
+        }
+    }
+    /// Axis through `position`, rounded to the nearest half column.
+    pub fn at(position: f32) -> Self {
+        SymmetryAxis {
+            column_sum: (2.0 * position).round() as usize,
+        }
+    }
+    pub fn position(&self) -> f32 {
+        self.column_sum as f32 / 2.0
+    }
+    /// Column mirrored to `column`, or `None` if it would lie left of
+    /// column 0.
+    pub fn mirror(&self, column: usize) -> Option<usize> {
+        self.column_sum.checked_sub(column)
+    }
+    /// Columns left of the axis whose mirror is inside a matrix of `width`.
+    fn left_half(&self, width: usize) -> std::ops::Range<usize> {
+        (self.column_sum + 1).saturating_sub(width)..self.column_sum.div_ceil(2)
+    }
+}
+
+/// Cheapest pair of seams mirrored around `axis`, left seam first, with the
+/// total energy of both. The search runs over the columns left of the axis
+/// with the energy of every pixel and its mirror added up, so neither seam
+/// crosses the axis. Returns `None` if no column has a mirror in the matrix
+/// and fails if the matrix has no rows.
+pub fn extract_mirrored_vertical_seams(
+    energy_matrix: &Matrix<GradientMagnitudePoint>,
+    axis: &SymmetryAxis,
+    rng: &mut ThreadRng,
+    avoid_inserted: bool,
+) -> Result<Option<(VerticalSeam, VerticalSeam, f32)>> {
+    let width = energy_matrix.width();
+    let half = axis.left_half(width);
+    if half.is_empty() {
+        return Ok(None);
+    }
+
+    let half_matrix = Matrix::new(
+        (0..energy_matrix.height())
+            .flat_map(|row| half.clone().map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let point = energy_matrix.vector[row * width + column];
+                let mirrored = energy_matrix.vector[row * width + axis.column_sum - column];
+                GradientMagnitudePoint {
+                    value: point.value + mirrored.value,
+                    is_inserted: point.is_inserted || mirrored.is_inserted,
+                }
+            })
+            .collect(),
+        half.len(),
+    );
+    let (seam, total_energy) = half_matrix.extract_vertical_seam(rng, avoid_inserted)?;
+
+    let left = VerticalSeam {
+        columns: seam
+            .columns
+            .iter()
+            .map(|column| column + half.start)
+            .collect(),
+    };
+    let right = VerticalSeam {
+        columns: left
+            .columns
+            .iter()
+            .map(|column| axis.column_sum - column)
+            .collect(),
+    };
+    Ok(Some((left, right, total_energy)))
+}
+
+/// Removes mirrored seam pairs, found on `energy`, until `matrix` is
+/// `target_width` wide and returns where the axis ended up. An odd difference
+/// is finished by removing the axis column if the axis runs through one,
+/// otherwise the result stays one column wider than asked so it is still
+/// symmetric.
+pub fn carve_symmetric_width(
+    matrix: &mut Matrix<CustomColor>,
+    mut axis: SymmetryAxis,
+    target_width: usize,
+    energy: &Energy,
+    rng: &mut ThreadRng,
+) -> Result<SymmetryAxis> {
+    while matrix.width() >= target_width + 2 {
+        let Some((left, right, _)) =
+            extract_mirrored_vertical_seams(&energy.color_energy(matrix), &axis, rng, false)?
+        else {
+            break;
+        };
+        matrix.carve_vertical_seam(&right)?;
+        matrix.carve_vertical_seam(&left)?;
+        axis.column_sum -= 2;
+    }
+
+    if matrix.width() == target_width + 1 && axis.column_sum.is_multiple_of(2) {
+        matrix.carve_vertical_seam(&VerticalSeam {
+            columns: vec![axis.column_sum / 2; matrix.height()],
+        })?;
+        axis.column_sum -= 1;
+    }
+    Ok(axis)
+}
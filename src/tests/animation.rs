@@ -0,0 +1,109 @@
+use ::image::{codecs::gif::GifDecoder, AnimationDecoder, Rgba};
+use std::{env, fs, fs::File, io::BufReader};
+
+use crate::{
+    animation::{record_resize, CarvingRecorder},
+    energy::Energy,
+    structs::{
+        color::CustomColor, interpolation::Interpolation, matrix::Matrix, window_size::WindowSize,
+    },
+};
+
+#[test]
+fn recorded_frames_are_padded_to_the_original_canvas() {
+    let mut matrix = Matrix::new(
+        (0..20)
+            .map(|index| CustomColor {
+                r: (index % 3) as f32 / 2.0,
+                g: (index % 5) as f32 / 4.0,
+                b: 0.5,
+                is_inserted: false,
+            })
+            .collect(),
+        5,
+    );
+
+    let recorder = record_resize(
+        &mut matrix,
+        &Energy::default(),
+        Interpolation::default(),
+        &WindowSize {
+            width: 3,
+            height: 4,
+        },
+        true,
+    )
+    .unwrap();
+
+    assert_eq!(recorder.frame_count(), 3);
+    let frames = recorder.padded_frames();
+    assert!(frames
+        .iter()
+        .all(|frame| frame.width() == 5 && frame.height() == 4));
+    // the first frame shows the seam, the last one is the unmarked result
+    assert!(frames[0]
+        .pixels()
+        .any(|pixel| *pixel == Rgba([255, 0, 0, 255])));
+    assert_eq!(frames[2].get_pixel(4, 0)[3], 0);
+    assert_eq!(frames[2].get_pixel(2, 0)[3], 255);
+}
+
+#[test]
+fn saved_animations_hold_every_padded_frame() {
+    let mut matrix = Matrix::new(
+        (0..24)
+            .map(|index| CustomColor {
+                r: (index % 7) as f32 / 6.0,
+                g: (index % 3) as f32 / 2.0,
+                b: 0.25,
+                is_inserted: false,
+            })
+            .collect(),
+        6,
+    );
+    let recorder = record_resize(
+        &mut matrix,
+        &Energy::default(),
+        Interpolation::default(),
+        &WindowSize {
+            width: 4,
+            height: 4,
+        },
+        false,
+    )
+    .unwrap();
+    let directory = env::temp_dir().join(format!("seam_carver_animation_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let (gif_path, apng_path) = (directory.join("carving.gif"), directory.join("carving.png"));
+
+    recorder.save_gif(&gif_path, 40).unwrap();
+    recorder.save_apng(&apng_path, 40).unwrap();
+    let gif_frames = GifDecoder::new(BufReader::new(File::open(&gif_path).unwrap()))
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    let mut apng = png::Decoder::new(BufReader::new(File::open(&apng_path).unwrap()))
+        .read_info()
+        .unwrap();
+    let apng_size = (apng.info().width, apng.info().height);
+    let apng_frames = apng.info().animation_control.unwrap().num_frames;
+    let mut buffer = vec![0; apng.output_buffer_size()];
+    for _ in 0..apng_frames {
+        apng.next_frame(&mut buffer).unwrap();
+    }
+    let empty = CarvingRecorder::new(false);
+    let empty_saved = [
+        empty.save_gif(&directory.join("empty.gif"), 40).is_err(),
+        empty.save_apng(&directory.join("empty.png"), 40).is_err(),
+    ];
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(gif_frames.len(), 3);
+    assert!(gif_frames
+        .iter()
+        .all(|frame| frame.buffer().dimensions() == (6, 4)));
+    assert_eq!(apng_frames, 3);
+    assert_eq!(apng_size, (6, 4));
+    assert_eq!(empty_saved, [true, true]);
+}
@@ -0,0 +1,118 @@
+use ::image::RgbaImage;
+use ::rand::{thread_rng, Rng};
+use std::{env, fs, path::PathBuf};
+
+use crate::{
+    batch::{write_manifest, Batch, BatchRecord},
+    energy::Energy,
+    structs::{
+        interpolation::Interpolation,
+        target_size::{Dimension, Target, TargetSize},
+    },
+};
+
+fn noise(width: u32, height: u32) -> RgbaImage {
+    let mut rng = thread_rng();
+    RgbaImage::from_fn(width, height, |_, _| {
+        ::image::Rgba([rng.gen(), rng.gen(), rng.gen(), 255])
+    })
+}
+
+#[test]
+fn batches_mirror_the_input_tree() {
+    let directory = env::temp_dir().join(format!("seam_carver_batch_{}", std::process::id()));
+    let input = directory.join("input");
+    fs::create_dir_all(input.join("nested")).unwrap();
+    noise(12, 8).save(input.join("wide.png")).unwrap();
+    noise(6, 9).save(input.join("nested/tall.png")).unwrap();
+    fs::write(input.join("nested/broken.png"), "not a png").unwrap();
+    fs::write(input.join("notes.txt"), "skipped").unwrap();
+    let batch = Batch {
+        input: input.clone(),
+        output: directory.join("output"),
+        targets: vec![
+            Target {
+                size: TargetSize::Size {
+                    width: Dimension::Pixels(8),
+                    height: Dimension::Pixels(8),
+                },
+                fit: false,
+            },
+            Target {
+                size: TargetSize::Aspect {
+                    width: 1,
+                    height: 1,
+                },
+                fit: false,
+            },
+        ],
+        energy: Energy::default(),
+        interpolation: Interpolation::default(),
+        workers: 3,
+    };
+
+    let records = batch.run().unwrap();
+    let manifest = directory.join("manifest.csv");
+    write_manifest(&records, &manifest).unwrap();
+    let csv = fs::read_to_string(&manifest).unwrap();
+    let tall = ::image::open(directory.join("output/nested/tall_6x6.png")).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    let inputs = records
+        .iter()
+        .map(|record| record.input.strip_prefix(&input).unwrap().to_path_buf())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        inputs,
+        ["nested/broken.png", "nested/broken.png", "nested/tall.png"]
+            .iter()
+            .chain(&["nested/tall.png", "wide.png", "wide.png"])
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+    );
+    assert!(records[0].error.is_some());
+    assert_eq!((tall.width(), tall.height()), (6, 6));
+    assert_eq!((records[2].carved_seams, records[2].inserted_seams), (1, 2));
+    assert_eq!((records[5].width, records[5].height), (8, 8));
+    assert_eq!(records[5].carved_seams, 4);
+    assert!(records[5].error.is_none());
+    // the square aspect of the wide image resolves to the same size
+    assert_eq!(records[5].output, directory.join("output/wide_8x8_2.png"));
+    assert_ne!(records[4].output, records[5].output);
+    assert_eq!(csv.lines().count(), 7);
+}
+
+#[test]
+fn outputs_inside_the_input_are_not_processed_again() {
+    let directory =
+        env::temp_dir().join(format!("seam_carver_batch_nested_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    noise(6, 4).save(directory.join("wide.png")).unwrap();
+    let batch = Batch {
+        input: directory.clone(),
+        output: directory.join("resized"),
+        targets: vec![Target {
+            size: TargetSize::Size {
+                width: Dimension::Pixels(4),
+                height: Dimension::Pixels(4),
+            },
+            fit: false,
+        }],
+        energy: Energy::default(),
+        interpolation: Interpolation::default(),
+        workers: 1,
+    };
+
+    let first = batch.run().unwrap();
+    let second = batch.run().unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    let inputs = |records: &[BatchRecord]| {
+        records
+            .iter()
+            .map(|record| record.input.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(inputs(&first), [directory.join("wide.png")]);
+    assert_eq!(inputs(&second), inputs(&first));
+}
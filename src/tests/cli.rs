@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use crate::{
+    cli::{parse_args, parse_batch_args},
+    energy::{DepthWeights, EnergyFunction},
+    structs::{
+        interpolation::Interpolation,
+        target_size::{Dimension, Target, TargetSize},
+    },
+    utils::BorderMode,
+};
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn defaults_to_gradient_energy_on_image_png() {
+    let options = parse_args(args("")).unwrap();
+
+    assert_eq!(options.image, PathBuf::from("image.png"));
+    assert_eq!(options.depth, None);
+    assert_eq!(options.energy.border, BorderMode::Zero);
+    assert_eq!(options.interpolation, Interpolation::Average);
+    assert_eq!(options.energy.function, EnergyFunction::GradientMagnitude);
+    assert_eq!(options.target, Target::default());
+}
+
+#[test]
+fn depth_map_selects_depth_energy() {
+    let options = parse_args(args("photo.png --depth depth.png --nearness-weight 2")).unwrap();
+
+    assert_eq!(options.image, PathBuf::from("photo.png"));
+    assert_eq!(options.depth, Some(PathBuf::from("depth.png")));
+    assert_eq!(
+        options.energy.function,
+        EnergyFunction::DepthAware(DepthWeights {
+            nearness: 2.0,
+            ..DepthWeights::default()
+        })
+    );
+    assert_eq!(options.energy.border, BorderMode::Replicate);
+
+    let options = parse_args(args("--depth depth.png --energy gradient")).unwrap();
+    assert_eq!(options.energy.function, EnergyFunction::GradientMagnitude);
+}
+
+#[test]
+fn energy_names_select_energy_functions() {
+    let options = parse_args(args("--energy saliency --saliency-weight 0.25")).unwrap();
+
+    assert_eq!(
+        options.energy.function,
+        EnergyFunction::Saliency { weight: 0.25 }
+    );
+
+    let options = parse_args(args("--energy lab")).unwrap();
+    assert_eq!(options.energy.function, EnergyFunction::LabDifference);
+
+    let options = parse_args(args("--energy lines --line-weight 3")).unwrap();
+    assert_eq!(
+        options.energy.function,
+        EnergyFunction::LinePreserving { weight: 3.0 }
+    );
+
+    let options = parse_args(args("--border reflect")).unwrap();
+    assert_eq!(options.energy.border, BorderMode::Reflect);
+
+    let options = parse_args(args("--interpolation gradient")).unwrap();
+    assert_eq!(options.interpolation, Interpolation::GradientPreserving);
+}
+
+#[test]
+fn target_options_select_target_sizes() {
+    let options = parse_args(args("--width 1200 --keep-height")).unwrap();
+    assert_eq!(
+        options.target.size,
+        TargetSize::Size {
+            width: Dimension::Pixels(1200),
+            height: Dimension::Keep
+        }
+    );
+
+    let options = parse_args(args("--percent 80x100 --fit")).unwrap();
+    assert_eq!(
+        options.target,
+        Target {
+            size: TargetSize::Size {
+                width: Dimension::Percent(80.0),
+                height: Dimension::Percent(100.0)
+            },
+            fit: true
+        }
+    );
+
+    let options = parse_args(args("--aspect 16:9")).unwrap();
+    assert_eq!(
+        options.target.size,
+        TargetSize::Aspect {
+            width: 16,
+            height: 9
+        }
+    );
+}
+
+#[test]
+fn invalid_arguments_are_rejected() {
+    assert!(parse_args(args("--energy depth")).is_err());
+    assert!(parse_args(args("--energy sobel")).is_err());
+    assert!(parse_args(args("--border mirror")).is_err());
+    assert!(parse_args(args("--interpolation nearest")).is_err());
+    assert!(parse_args(args("--depth-weight heavy")).is_err());
+    assert!(parse_args(args("--verbose")).is_err());
+    assert!(parse_args(args("--width 0")).is_err());
+    assert!(parse_args(args("--width 100 --keep-width")).is_err());
+    assert!(parse_args(args("--aspect 16x9")).is_err());
+    assert!(parse_args(args("--aspect 16:9 --percent 50")).is_err());
+    assert!(parse_args(args("--percent inf")).is_err());
+    assert!(parse_args(args("--percent NaNx50")).is_err());
+    assert!(parse_args(args("--percent 50x-10")).is_err());
+    assert!(parse_args(args("--percent 0")).is_err());
+    assert!(parse_args(args("first.png second.png")).is_err());
+}
+
+#[test]
+fn batch_arguments_collect_targets() {
+    let options = parse_batch_args(args(
+        "photos out --size 300x200 --percent 50 --aspect 4:3 --fit --border replicate --interpolation duplicate --workers 2",
+    ))
+    .unwrap();
+
+    assert_eq!(options.batch.input, PathBuf::from("photos"));
+    assert_eq!(options.manifest, PathBuf::from("out/manifest.json"));
+    assert_eq!(options.batch.workers, 2);
+    assert_eq!(options.batch.energy.border, BorderMode::Replicate);
+    assert_eq!(options.batch.interpolation, Interpolation::Duplicate);
+    assert_eq!(
+        options.batch.targets[1],
+        Target {
+            size: TargetSize::Size {
+                width: Dimension::Percent(50.0),
+                height: Dimension::Percent(50.0)
+            },
+            fit: true
+        }
+    );
+    assert_eq!(options.batch.targets.len(), 3);
+
+    assert!(parse_batch_args(args("photos --size 300x200")).is_err());
+    assert!(parse_batch_args(args("photos out")).is_err());
+    assert!(parse_batch_args(args("photos out --size 300")).is_err());
+    assert!(parse_batch_args(args("photos out --percent inf")).is_err());
+    assert!(parse_batch_args(args("photos out --width 300 --keep-width")).is_err());
+}
+
+#[test]
+fn batch_pixel_sides_add_one_target() {
+    let options =
+        parse_batch_args(args("photos out --size 300x200 --width 1200 --keep-height")).unwrap();
+
+    assert_eq!(
+        options.batch.targets,
+        [
+            Target {
+                size: TargetSize::Size {
+                    width: Dimension::Pixels(300),
+                    height: Dimension::Pixels(200)
+                },
+                fit: false
+            },
+            Target {
+                size: TargetSize::Size {
+                    width: Dimension::Pixels(1200),
+                    height: Dimension::Keep
+                },
+                fit: false
+            }
+        ]
+    );
+}
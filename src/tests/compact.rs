@@ -0,0 +1,108 @@
+use ::rand::thread_rng;
+
+use crate::{
+    structs::{
+        color::CustomColor,
+        compact::{CompactColor, CompactImage, InsertedMask},
+        matrix::{HorizontalSeam, Matrix, VerticalSeam},
+    },
+    utils::gradient_magnitude,
+};
+
+fn gray(value: u8) -> CompactColor {
+    CompactColor {
+        r: value,
+        g: value,
+        b: value,
+        a: 255,
+    }
+}
+
+#[test]
+fn compact_pixel_sizes() {
+    assert_eq!(std::mem::size_of::<CompactColor>(), 4);
+    assert_eq!(
+        std::mem::size_of::<crate::structs::compact::CompactEnergy>(),
+        2
+    );
+}
+
+#[test]
+fn compact_insertion_matches_custom_color() {
+    let pixels = Matrix::new(
+        Vec::from([0, 100, 200, 50, 150, 250, 25, 125, 225].map(gray)),
+        3,
+    );
+    let seam = VerticalSeam {
+        columns: vec![0, 1, 1],
+    };
+
+    let mut compact = CompactImage::new(pixels.clone());
+    compact.insert_vertical_seam(&seam).unwrap();
+    let mut full = CompactImage::new(pixels).to_matrix();
+    full.insert_vertical_seam(&seam).unwrap();
+
+    assert_eq!(compact.width(), 4);
+    assert_eq!(compact.to_matrix().vector, full.vector);
+    for (index, color) in full.vector.iter().enumerate() {
+        assert_eq!(
+            compact.inserted.get(index / 4, index % 4),
+            color.is_inserted
+        );
+    }
+}
+
+#[test]
+fn inserted_mask_follows_carving() {
+    let mut mask = InsertedMask::new(3, 3);
+    mask.set(0, 2, true);
+    mask.set(1, 0, true);
+    mask.set(2, 1, true);
+
+    mask.carve_vertical_seam(&VerticalSeam {
+        columns: vec![0, 0, 1],
+    })
+    .unwrap();
+    assert_eq!(
+        (0..3)
+            .flat_map(|row| (0..2).map(move |column| (row, column)))
+            .map(|(row, column)| mask.get(row, column))
+            .collect::<Vec<bool>>(),
+        [false, true, false, false, false, false]
+    );
+
+    mask.carve_horizontal_seam(&HorizontalSeam { rows: vec![1, 0] })
+        .unwrap();
+    assert_eq!(
+        [
+            mask.get(0, 0),
+            mask.get(0, 1),
+            mask.get(1, 0),
+            mask.get(1, 1)
+        ],
+        [false, false, false, false]
+    );
+}
+
+#[test]
+fn compact_seam_matches_full_precision_seam() {
+    let matrix = Matrix::new(
+        Vec::from(
+            [0, 255, 255, 255, 0, 255, 255, 255, 0].map(|value| CustomColor::from(gray(value))),
+        ),
+        3,
+    );
+    let compact = CompactImage::from_matrix(&matrix);
+    let mut rng = thread_rng();
+
+    let (_, full_energy) = gradient_magnitude(&matrix)
+        .extract_vertical_seam(&mut rng, false)
+        .unwrap();
+    let (compact_seam, compact_energy) = compact
+        .energy()
+        .extract_vertical_seam(&mut rng, &compact.inserted, false)
+        .unwrap();
+
+    assert_eq!(compact_seam.columns.len(), 3);
+    assert!((full_energy - compact_energy).abs() < 1e-3);
+}
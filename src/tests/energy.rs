@@ -0,0 +1,208 @@
+use crate::{
+    energy::{frequency_tuned_saliency, DepthWeights, Energy, EnergyFunction, DEPTH_LAYER},
+    structs::{
+        color::CustomColor,
+        layers::{Layer, LayeredImage},
+        matrix::Matrix,
+    },
+    utils::{gradient_magnitude, load_luma_matrix, BorderMode},
+};
+use ::image::{ImageBuffer, Luma};
+use std::{env, fs};
+
+fn flat_image(width: usize, height: usize) -> Matrix<CustomColor> {
+    Matrix::new(
+        vec![
+            CustomColor {
+                r: 0.5,
+                g: 0.5,
+                b: 0.5,
+                is_inserted: false,
+            };
+            width * height
+        ],
+        width,
+    )
+}
+
+#[test]
+fn depth_aware_energy_prefers_the_background() {
+    let mut image = LayeredImage::new(flat_image(4, 3));
+    // foreground on the left, background on the right
+    image
+        .add_layer(
+            DEPTH_LAYER,
+            Layer::averaged(Matrix::new(
+                vec![1.0, 1.0, 9.0, 9.0, 1.0, 1.0, 9.0, 9.0, 1.0, 1.0, 9.0, 9.0],
+                4,
+            )),
+        )
+        .ok()
+        .unwrap();
+
+    let energy = Energy::from(EnergyFunction::DepthAware(DepthWeights {
+        discontinuity: 0.0,
+        nearness: 1.0,
+    }))
+    .energy_matrix(&image);
+    let (seam, _) = energy
+        .extract_vertical_seam(&mut ::rand::thread_rng(), false)
+        .unwrap();
+
+    assert!(seam.columns.iter().all(|column| *column >= 2));
+}
+
+#[test]
+fn depth_discontinuities_add_energy() {
+    let mut image = LayeredImage::new(flat_image(4, 1));
+    image
+        .add_layer(
+            DEPTH_LAYER,
+            Layer::averaged(Matrix::new(vec![0.0, 0.0, 1.0, 1.0], 4)),
+        )
+        .ok()
+        .unwrap();
+
+    let energy = Energy::from(EnergyFunction::DepthAware(DepthWeights {
+        discontinuity: 1.0,
+        nearness: 0.0,
+    }))
+    .energy_matrix(&image);
+    let gradient = gradient_magnitude(&image.color);
+
+    assert!(energy.vector[1].value > gradient.vector[1].value);
+    assert!(energy.vector[2].value > gradient.vector[2].value);
+}
+
+#[test]
+fn missing_depth_layer_falls_back_to_gradient() {
+    let image = LayeredImage::new(flat_image(3, 3));
+
+    let depth_energy = Energy::from(EnergyFunction::DepthAware(DepthWeights::default()));
+    let energy = depth_energy.energy_matrix(&image);
+    let gradient = Energy {
+        function: EnergyFunction::GradientMagnitude,
+        ..depth_energy
+    }
+    .energy_matrix(&image);
+
+    assert!(energy
+        .vector
+        .iter()
+        .zip(gradient.vector.iter())
+        .all(|(first, second)| first.value == second.value));
+}
+
+#[test]
+fn saliency_highlights_a_smooth_subject() {
+    // a soft red patch in the middle of a grey image
+    let mut color = flat_image(9, 9);
+    for row in 3..6 {
+        for column in 3..6 {
+            color.vector[row * 9 + column] = CustomColor {
+                r: 0.9,
+                g: 0.3,
+                b: 0.3,
+                is_inserted: false,
+            };
+        }
+    }
+
+    let saliency = frequency_tuned_saliency(&color);
+
+    assert_eq!(saliency.vector[4 * 9 + 4], 1.0);
+    assert!(saliency.vector[0] < 0.2);
+
+    let image = LayeredImage::new(color);
+    let energy = Energy::from(EnergyFunction::Saliency { weight: 2.0 }).energy_matrix(&image);
+    let gradient = gradient_magnitude(&image.color);
+    assert!(energy.vector[4 * 9 + 4].value >= gradient.vector[4 * 9 + 4].value + 2.0);
+}
+
+#[test]
+fn lab_difference_sees_isoluminant_edges() {
+    // red and green with the same luma, split down the middle
+    let red = CustomColor {
+        r: 1.0,
+        g: 0.0,
+        b: 0.0,
+        is_inserted: false,
+    };
+    let green = CustomColor {
+        r: 0.0,
+        g: 0.299 / 0.587,
+        b: 0.0,
+        is_inserted: false,
+    };
+    let color = Matrix::new(
+        (0..36)
+            .map(|index| if index % 6 < 3 { red } else { green })
+            .collect(),
+        6,
+    );
+
+    let luma_energy = gradient_magnitude(&color);
+    let lab_energy =
+        Energy::from(EnergyFunction::LabDifference).energy_matrix(&LayeredImage::new(color));
+
+    assert!(luma_energy.vector[2 * 6 + 2].value < 1e-6);
+    assert!(lab_energy.vector[2 * 6 + 2].value > 0.5);
+
+    let white = CustomColor {
+        r: 1.0,
+        g: 1.0,
+        b: 1.0,
+        is_inserted: false,
+    }
+    .to_lab();
+    assert!((white[0] - 100.0).abs() < 0.01 && white[1].abs() < 0.01 && white[2].abs() < 0.01);
+}
+
+#[test]
+fn border_modes_keep_flat_borders_flat() {
+    let image = LayeredImage::new(flat_image(4, 3));
+
+    let zero = Energy::default().energy_matrix(&image);
+    assert!(zero.vector[0].value > 0.0);
+
+    for border in [BorderMode::Replicate, BorderMode::Reflect, BorderMode::Wrap] {
+        for function in [
+            EnergyFunction::GradientMagnitude,
+            EnergyFunction::LabDifference,
+        ] {
+            let energy = Energy { function, border }.energy_matrix(&image);
+            assert!(energy.vector.iter().all(|point| point.value.abs() < 1e-6));
+        }
+    }
+}
+
+#[test]
+fn border_modes_pick_neighbours() {
+    assert_eq!(BorderMode::Zero.neighbour(0, -1, 4), None);
+    assert_eq!(BorderMode::Replicate.neighbour(0, -1, 4), Some(0));
+    assert_eq!(BorderMode::Reflect.neighbour(0, -1, 4), Some(1));
+    assert_eq!(BorderMode::Reflect.neighbour(3, 1, 4), Some(2));
+    assert_eq!(BorderMode::Wrap.neighbour(3, 1, 4), Some(0));
+    assert_eq!(BorderMode::Wrap.neighbour(1, 1, 4), Some(2));
+}
+
+#[test]
+fn depth_maps_keep_sixteen_bits() {
+    let directory = env::temp_dir().join(format!("seam_carver_energy_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("depth.png");
+    let depth: ImageBuffer<Luma<u16>, Vec<u16>> =
+        ImageBuffer::from_fn(3, 1, |x, _| Luma([1000 + x as u16]));
+    depth.save(&path).unwrap();
+
+    let loaded = load_luma_matrix(&path).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!((loaded.width(), loaded.height()), (3, 1));
+    assert!(loaded.vector.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!((loaded.vector[0] - 1000.0 / 65535.0).abs() < 1e-6);
+    assert_eq!(
+        Energy::from(EnergyFunction::DepthAware(DepthWeights::default())).border,
+        BorderMode::Replicate
+    );
+}
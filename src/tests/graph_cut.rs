@@ -0,0 +1,158 @@
+use ::rand::{thread_rng, Rng};
+
+use crate::{
+    graph_cut::{forward_graph_cut_vertical_seams, graph_cut_vertical_seams},
+    seam_finder::{DynamicProgramming, GraphCut, SeamFinder},
+    structs::matrix::Matrix,
+    utils::GradientMagnitudePoint,
+};
+
+fn random_energy_matrix(width: usize, height: usize) -> Matrix<GradientMagnitudePoint> {
+    let mut rng = thread_rng();
+    Matrix::new(
+        (0..width * height)
+            .map(|_| GradientMagnitudePoint {
+                value: rng.gen_range(0..10) as f32,
+                is_inserted: false,
+            })
+            .collect(),
+        width,
+    )
+}
+
+#[test]
+fn graph_cut_matches_dynamic_programming() {
+    let mut rng = thread_rng();
+    for _ in 0..20 {
+        let energy_matrix = random_energy_matrix(7, 5);
+
+        let (dp_seam, dp_energy) = DynamicProgramming
+            .find_vertical_seam(&energy_matrix, &mut rng, false)
+            .unwrap();
+        let (cut_seam, cut_energy) = GraphCut::default()
+            .find_vertical_seam(&energy_matrix, &mut rng, false)
+            .unwrap();
+        assert_eq!(cut_energy, dp_energy);
+        assert_eq!(cut_seam.columns.len(), dp_seam.columns.len());
+        assert!(cut_seam
+            .columns
+            .windows(2)
+            .all(|pair| pair[0].abs_diff(pair[1]) <= 1));
+
+        let (_, dp_energy) = DynamicProgramming
+            .find_horizontal_seam(&energy_matrix, &mut rng, false)
+            .unwrap();
+        let (cut_seam, cut_energy) = GraphCut::default()
+            .find_horizontal_seam(&energy_matrix, &mut rng, false)
+            .unwrap();
+        assert_eq!(cut_energy, dp_energy);
+        assert_eq!(cut_seam.rows.len(), 7);
+    }
+}
+
+#[test]
+fn graph_cut_seam_surface_is_connected_in_time() {
+    let frames = (0..4)
+        .map(|_| {
+            let energy_matrix = random_energy_matrix(6, 6);
+            energy_matrix.seam_costs(false)
+        })
+        .collect::<Vec<Matrix<f32>>>();
+
+    let seams = graph_cut_vertical_seams(&frames);
+
+    assert_eq!(seams.len(), 4);
+    for pair in seams.windows(2) {
+        assert!(pair[0]
+            .columns
+            .iter()
+            .zip(pair[1].columns.iter())
+            .all(|(first, second)| first.abs_diff(*second) <= 1));
+    }
+}
+
+/// Forward energy of removing `columns` from `luma`, summed the way the
+/// dynamic programming formulation of forward energy sums it.
+fn forward_cost(luma: &Matrix<f32>, columns: &[usize]) -> f32 {
+    let width = luma.width();
+    let at = |row: usize, column: usize| luma.vector[row * width + column];
+    columns
+        .iter()
+        .enumerate()
+        .map(|(row, &column)| {
+            let mut cost = if column == 0 || column == width - 1 {
+                0.0
+            } else {
+                (at(row, column + 1) - at(row, column - 1)).abs()
+            };
+            if row > 0 && columns[row - 1] == column + 1 {
+                cost += (at(row - 1, column) - at(row, column + 1)).abs();
+            } else if row > 0 && columns[row - 1] + 1 == column {
+                cost += (at(row - 1, column) - at(row, column - 1)).abs();
+            }
+            cost
+        })
+        .sum()
+}
+
+fn all_seams(width: usize, height: usize) -> Vec<Vec<usize>> {
+    let mut seams = (0..width).map(|column| vec![column]).collect::<Vec<_>>();
+    for _ in 1..height {
+        seams = seams
+            .into_iter()
+            .flat_map(|seam| {
+                let last = *seam.last().unwrap();
+                (last.saturating_sub(1)..=(last + 1).min(width - 1)).map(move |column| {
+                    let mut longer = seam.clone();
+                    longer.push(column);
+                    longer
+                })
+            })
+            .collect();
+    }
+    seams
+}
+
+#[test]
+fn forward_energy_cut_is_optimal_and_differs_from_backward() {
+    let mut rng = thread_rng();
+    for _ in 0..10 {
+        let luma = Matrix::new((0..5 * 4).map(|_| rng.gen_range(0..4) as f32).collect(), 5);
+        let costs = Matrix::new(vec![1.0; 5 * 4], 5);
+        let seam = forward_graph_cut_vertical_seams(&[costs], std::slice::from_ref(&luma))
+            .pop()
+            .unwrap();
+        let best = all_seams(5, 4)
+            .iter()
+            .map(|columns| forward_cost(&luma, columns))
+            .fold(f32::INFINITY, f32::min);
+        assert_eq!(forward_cost(&luma, &seam.columns), best);
+    }
+
+    // the cheapest pixels separate a dark from a bright half, so removing
+    // them creates a new edge in every row
+    let luma = Matrix::new([0.0, 0.0, 0.0, 1.0, 1.0].repeat(4), 5);
+    let energy_matrix = Matrix::new(
+        [1.0, 1.0, 0.0, 1.0, 1.0]
+            .repeat(4)
+            .into_iter()
+            .map(|value| GradientMagnitudePoint {
+                value,
+                is_inserted: false,
+            })
+            .collect(),
+        5,
+    );
+    let (dp_seam, dp_energy) = DynamicProgramming
+        .find_vertical_seam(&energy_matrix, &mut rng, false)
+        .unwrap();
+    let (cut_seam, cut_energy) = GraphCut::forward(&luma)
+        .find_vertical_seam(&energy_matrix, &mut rng, false)
+        .unwrap();
+
+    assert_eq!(dp_seam.columns, vec![2; 4]);
+    assert_eq!(dp_energy, 0.0);
+    assert_eq!(forward_cost(&luma, &cut_seam.columns), 0.0);
+    assert!(!cut_seam.columns.contains(&2));
+    assert_eq!(cut_energy, 4.0);
+}
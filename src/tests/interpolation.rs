@@ -0,0 +1,46 @@
+use crate::structs::{
+    interpolation::Interpolation,
+    matrix::{Matrix, VerticalSeam},
+};
+
+#[test]
+fn modes_fill_between_the_seam_pixel_and_its_neighbour() {
+    let row = [0.0, 1.0, 2.0, 3.0, 10.0];
+
+    assert_eq!(Interpolation::Average.fill(&row, 2), 2.0);
+    assert_eq!(Interpolation::Duplicate.fill(&row, 2), 2.0);
+    assert_eq!(Interpolation::Linear.fill(&row, 2), 2.5);
+    assert_eq!(Interpolation::CatmullRom.fill(&row, 2), 2.125);
+    // the ramp on the left is smoother than the jump to 10.0, so it continues
+    assert_eq!(Interpolation::GradientPreserving.fill(&row, 2), 2.5);
+    // next to the jump the flat border on the right is smoother
+    assert_eq!(Interpolation::GradientPreserving.fill(&row, 3), 10.0);
+}
+
+#[test]
+fn modes_handle_the_image_border() {
+    for interpolation in [
+        Interpolation::Average,
+        Interpolation::Duplicate,
+        Interpolation::Linear,
+        Interpolation::CatmullRom,
+        Interpolation::GradientPreserving,
+    ] {
+        assert_eq!(interpolation.fill(&[5.0], 0), 5.0, "{:?}", interpolation);
+    }
+    assert_eq!(Interpolation::Linear.fill(&[4.0, 6.0], 1), 6.0);
+}
+
+#[test]
+fn duplicating_keeps_edges_sharp() {
+    let mut matrix = Matrix::new(vec![0.0, 0.0, 1.0, 1.0], 4);
+
+    matrix
+        .insert_vertical_seam_interpolated(
+            &VerticalSeam { columns: vec![1] },
+            Interpolation::Duplicate,
+        )
+        .unwrap();
+
+    assert_eq!(matrix.vector, [0.0, 0.0, 0.0, 1.0, 1.0]);
+}
@@ -0,0 +1,119 @@
+use crate::{
+    error::Error,
+    structs::{
+        color::CustomColor,
+        layers::{InsertionPolicy, Layer, LayeredImage},
+        matrix::{HorizontalSeam, Matrix, VerticalSeam},
+    },
+};
+
+fn layered_image() -> LayeredImage {
+    let mut image = LayeredImage::new(Matrix::new(
+        vec![
+            CustomColor {
+                r: 0.5,
+                g: 0.5,
+                b: 0.5,
+                is_inserted: false,
+            };
+            9
+        ],
+        3,
+    ));
+    image
+        .add_layer(
+            "depth",
+            Layer::averaged(Matrix::new(
+                vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0],
+                3,
+            )),
+        )
+        .ok()
+        .unwrap();
+    image
+        .add_layer(
+            "labels",
+            Layer::majority(Matrix::new(vec![1u8, 2, 2, 1, 1, 2, 3, 1, 2], 3)),
+        )
+        .ok()
+        .unwrap();
+    image
+        .add_layer(
+            "uv",
+            Layer::copied(Matrix::new(
+                (0..9).map(|index| [index as f32, 0.0]).collect(),
+                3,
+            )),
+        )
+        .ok()
+        .unwrap();
+    image
+}
+
+#[test]
+fn seams_are_removed_from_every_layer() {
+    let mut image = layered_image();
+
+    image
+        .carve_vertical_seam(&VerticalSeam {
+            columns: vec![0, 1, 2],
+        })
+        .unwrap();
+    image
+        .carve_horizontal_seam(&HorizontalSeam { rows: vec![2, 1] })
+        .unwrap();
+
+    assert_eq!((image.width(), image.height()), (2, 2));
+    assert_eq!(
+        image.layer::<f32>("depth").unwrap().matrix.vector,
+        [2.0, 3.0, 4.0, 8.0]
+    );
+    assert_eq!(
+        image.layer::<u8>("labels").unwrap().matrix.vector,
+        [2, 2, 1, 1]
+    );
+}
+
+#[test]
+fn inserted_pixels_follow_layer_policies() {
+    let mut image = layered_image();
+
+    image
+        .insert_vertical_seam(&VerticalSeam {
+            columns: vec![1, 1, 1],
+        })
+        .unwrap();
+
+    let depth = image.layer::<f32>("depth").unwrap();
+    assert_eq!(depth.policy(), InsertionPolicy::Average);
+    assert_eq!(depth.matrix.vector[0..4], [1.0, 2.0, 2.0, 3.0]);
+    assert_eq!(
+        image.layer::<u8>("labels").unwrap().matrix.vector,
+        [1, 2, 2, 2, 1, 1, 1, 2, 3, 1, 1, 2]
+    );
+    assert_eq!(
+        image.layer::<[f32; 2]>("uv").unwrap().matrix.vector[0..4],
+        [[0.0, 0.0], [1.0, 0.0], [1.0, 0.0], [2.0, 0.0]]
+    );
+}
+
+#[test]
+fn layers_of_another_size_are_rejected() {
+    let mut image = layered_image();
+
+    assert!(image
+        .add_layer("depth", Layer::averaged(Matrix::new(vec![0.0; 4], 2)))
+        .is_err());
+    assert!(image.layer::<u16>("labels").is_none());
+}
+
+#[test]
+fn duplicate_layer_names_are_rejected() {
+    let mut image = layered_image();
+
+    assert!(matches!(
+        image.add_layer("labels", Layer::majority(Matrix::new(vec![0u8; 9], 3))),
+        Err(Error::DuplicateLayer(name)) if name == "labels"
+    ));
+    assert_eq!(image.layer::<u8>("labels").unwrap().matrix.vector[0], 1);
+}
@@ -0,0 +1,87 @@
+use std::{env, fs};
+
+use crate::{
+    structs::{
+        color::CustomColor,
+        linear::{srgb_to_linear, LinearColor},
+        matrix::{Matrix, VerticalSeam},
+        pixel::Pixel,
+    },
+    utils::{load_linear_matrix, save_linear_matrix},
+};
+
+fn linear(value: f32) -> LinearColor {
+    LinearColor {
+        r: value,
+        g: value,
+        b: value,
+        is_inserted: false,
+    }
+}
+
+#[test]
+fn luma_is_perceptual() {
+    let gray = CustomColor {
+        r: 0.5,
+        g: 0.5,
+        b: 0.5,
+        is_inserted: false,
+    };
+
+    assert!((linear(srgb_to_linear(0.5)).luma() - gray.luma()).abs() < 1e-4);
+    assert!((linear(1.0).luma() - 1.0).abs() < 1e-4);
+    assert!(linear(4.0).luma() > linear(2.0).luma());
+    assert!(linear(2.0).luma() > 1.0);
+}
+
+#[test]
+fn highlights_survive_carving_and_insertion() {
+    let mut matrix = Matrix::new(
+        vec![linear(0.0), linear(8.0), linear(16.0), linear(0.001)],
+        4,
+    );
+
+    matrix
+        .insert_vertical_seam(&VerticalSeam { columns: vec![1] })
+        .unwrap();
+    matrix
+        .carve_vertical_seam(&VerticalSeam { columns: vec![0] })
+        .unwrap();
+
+    assert_eq!(
+        matrix.vector,
+        [linear(8.0), linear(8.0), linear(16.0), linear(0.001)]
+    );
+}
+
+#[test]
+fn files_keep_their_precision() {
+    let directory = env::temp_dir().join(format!("seam_carver_linear_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let matrix = Matrix::new(
+        vec![
+            linear(0.0),
+            linear(0.00123),
+            linear(0.5),
+            linear(1.0),
+            linear(3.75),
+            linear(0.2),
+        ],
+        3,
+    );
+
+    let deep_path = directory.join("deep.png");
+    save_linear_matrix(&matrix, &deep_path).unwrap();
+    let deep = load_linear_matrix(&deep_path).unwrap();
+    let hdr_path = directory.join("hdr.exr");
+    save_linear_matrix(&matrix, &hdr_path).unwrap();
+    let hdr = load_linear_matrix(&hdr_path).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(deep.width(), 3);
+    // 16 bits keep shadows an 8-bit image would round to black
+    assert!((deep.vector[1].r - 0.00123).abs() < 1e-5);
+    assert!((deep.vector[2].r - 0.5).abs() < 1e-4);
+    assert_eq!(deep.vector[4].r, 1.0);
+    assert_eq!(hdr.vector, matrix.vector);
+}
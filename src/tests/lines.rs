@@ -0,0 +1,125 @@
+use crate::{
+    energy::{line_preserving_energy, Energy, EnergyFunction, LINES_LAYER},
+    lines::{detect_lines, LineDetection, LineGuide},
+    structs::{
+        color::CustomColor,
+        layers::LayeredImage,
+        matrix::{Matrix, VerticalSeam},
+    },
+    utils::{gradient_magnitude_with_border, BorderMode, GradientMagnitudePoint},
+};
+
+fn energy_with_edges(
+    width: usize,
+    height: usize,
+    is_edge: impl Fn(usize, usize) -> bool,
+) -> Matrix<GradientMagnitudePoint> {
+    Matrix::new(
+        (0..width * height)
+            .map(|index| GradientMagnitudePoint {
+                value: if is_edge(index % width, index / width) {
+                    1.0
+                } else {
+                    0.0
+                },
+                is_inserted: false,
+            })
+            .collect(),
+        width,
+    )
+}
+
+fn detection(min_votes: usize) -> LineDetection {
+    LineDetection {
+        min_votes,
+        ..LineDetection::default()
+    }
+}
+
+#[test]
+fn detects_horizontal_and_diagonal_lines() {
+    let energy = energy_with_edges(30, 30, |x, y| y == 10 || x == y);
+
+    let lines = detect_lines(&energy, &detection(20));
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().any(|line| line.votes == 30
+        && (line.theta - std::f32::consts::FRAC_PI_2).abs() < 1e-3
+        && line.rho == 10.0));
+    assert!(lines.iter().any(|line| line.votes >= 29
+        && (line.theta - 3.0 * std::f32::consts::FRAC_PI_4).abs() < 1e-3
+        && line.rho.abs() <= 1.0));
+}
+
+#[test]
+fn scattered_edges_are_not_lines() {
+    let energy = energy_with_edges(30, 30, |x, y| (x * 7 + y * 13) % 31 == 0);
+
+    assert!(detect_lines(&energy, &detection(10)).is_empty());
+}
+
+/// A dark band across the middle of a bright 30x30 image.
+fn band_image() -> Matrix<CustomColor> {
+    Matrix::new(
+        (0..30 * 30)
+            .map(|index| {
+                let value = if index / 30 == 15 { 0.0 } else { 1.0 };
+                CustomColor {
+                    r: value,
+                    g: value,
+                    b: value,
+                    is_inserted: false,
+                }
+            })
+            .collect(),
+        30,
+    )
+}
+
+#[test]
+fn line_pixels_get_extra_energy() {
+    let color = band_image();
+
+    let gradient = gradient_magnitude_with_border(&color, BorderMode::Replicate);
+    let energy = line_preserving_energy(&color, 2.0, &detection(20), BorderMode::Replicate);
+
+    // both edges of the band are within one suppression radius, so exactly
+    // one of them is kept as a line
+    let penalty =
+        |row: usize| energy.vector[row * 30 + 7].value - gradient.vector[row * 30 + 7].value;
+    assert_eq!(penalty(14) + penalty(16), 2.0);
+    assert_eq!(penalty(5), 0.0);
+}
+
+#[test]
+fn later_seams_cross_lines_where_earlier_ones_did() {
+    let mut image = LayeredImage::new(band_image());
+    let energy = Energy {
+        function: EnergyFunction::LinePreserving { weight: 2.0 },
+        border: BorderMode::Replicate,
+    };
+    energy.prepare(&mut image).unwrap();
+    let line_row = {
+        let guide = image.seam_layer::<LineGuide>(LINES_LAYER).unwrap();
+        assert_eq!(guide.lines().len(), 1);
+        guide.lines()[0].rho as usize
+    };
+
+    image
+        .carve_vertical_seam(&VerticalSeam {
+            columns: vec![20; 30],
+        })
+        .unwrap();
+    let (seam, _) = energy
+        .energy_matrix(&image)
+        .extract_vertical_seam(&mut ::rand::thread_rng(), false)
+        .unwrap();
+
+    let guide = image.seam_layer::<LineGuide>(LINES_LAYER).unwrap();
+    assert_eq!(guide.mask().width(), 29);
+    let crossed = (0..29)
+        .filter(|column| guide.mask().vector[line_row * 29 + column].crossed)
+        .collect::<Vec<usize>>();
+    assert_eq!(crossed, [19, 20]);
+    assert!(crossed.contains(&seam.columns[line_row]));
+}
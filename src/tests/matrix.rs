@@ -1,10 +1,15 @@
-use ::rand::thread_rng;
+use ::rand::{thread_rng, Rng};
 use std::fmt::Debug;
 
 use crate::{
+    error::Error,
+    seam_carver::{next_seam_operation, next_seam_operation_with_constraint, SeamOperation},
+    sequence::SeamSequence,
     structs::{
         color::CustomColor,
-        matrix::{HorizontalSeam, Matrix, VerticalSeam},
+        interpolation::Interpolation,
+        matrix::{HorizontalSeam, Matrix, SeamConstraint, VerticalSeam},
+        window_size::WindowSize,
     },
     utils::GradientMagnitudePoint,
 };
@@ -114,9 +119,11 @@ fn horizontal_carving() {
         4,
     );
     let mut output = matrix.clone();
-    output.carve_horizontal_seam(&HorizontalSeam {
-        rows: vec![0, 1, 1, 2],
-    });
+    output
+        .carve_horizontal_seam(&HorizontalSeam {
+            rows: vec![0, 1, 1, 2],
+        })
+        .unwrap();
     assert_matrices_equal(
         matrix,
         output,
@@ -164,9 +171,11 @@ fn vertical_carving() {
         4,
     );
     let mut output = matrix.clone();
-    output.carve_vertical_seam(&VerticalSeam {
-        columns: vec![0, 1, 2, 3],
-    });
+    output
+        .carve_vertical_seam(&VerticalSeam {
+            columns: vec![0, 1, 2, 3],
+        })
+        .unwrap();
     assert_matrices_equal(
         matrix,
         output,
@@ -202,7 +211,9 @@ fn horizontal_seam_extraction() {
         })),
         3,
     );
-    let (seam, total_energy) = energy_matrix.extract_horizontal_seam(&mut rng, false);
+    let (seam, total_energy) = energy_matrix
+        .extract_horizontal_seam(&mut rng, false)
+        .unwrap();
     assert_eq!(seam.rows, [0, 1, 2]);
     assert_eq!(total_energy, 0.0);
 }
@@ -219,11 +230,181 @@ fn vertical_seam_extraction() {
         })),
         3,
     );
-    let (seam, total_energy) = energy_matrix.extract_vertical_seam(&mut rng, false);
+    let (seam, total_energy) = energy_matrix
+        .extract_vertical_seam(&mut rng, false)
+        .unwrap();
     assert_eq!(seam.columns, [0, 1, 2]);
     assert_eq!(total_energy, 0.0);
 }
 
+#[test]
+fn seam_step_constraints() {
+    let mut rng = thread_rng();
+    let energy_matrix: Matrix<GradientMagnitudePoint> = Matrix::new(
+        Vec::from(
+            [
+                0.0, 5.0, 5.0, 5.0, //
+                5.0, 5.0, 5.0, 0.0, //
+                0.0, 5.0, 5.0, 5.0, //
+                5.0, 5.0, 5.0, 0.0,
+            ]
+            .map(|value| GradientMagnitudePoint {
+                value,
+                is_inserted: false,
+            }),
+        ),
+        4,
+    );
+
+    let (seam, total_energy) = energy_matrix
+        .extract_vertical_seam_with_constraint(
+            &mut rng,
+            false,
+            &SeamConstraint {
+                max_step: 3,
+                slope_penalty: 0.0,
+            },
+        )
+        .unwrap();
+    assert_eq!(seam.columns, [0, 3, 0, 3]);
+    assert_eq!(total_energy, 0.0);
+
+    let (seam, _) = energy_matrix
+        .extract_vertical_seam_with_constraint(
+            &mut rng,
+            false,
+            &SeamConstraint {
+                max_step: 0,
+                slope_penalty: 0.0,
+            },
+        )
+        .unwrap();
+    assert!(seam.columns.iter().all(|column| *column == seam.columns[0]));
+
+    let (seam, _) = energy_matrix
+        .extract_vertical_seam_with_constraint(
+            &mut rng,
+            false,
+            &SeamConstraint {
+                max_step: 3,
+                slope_penalty: 2.0,
+            },
+        )
+        .unwrap();
+    assert!(seam.columns.windows(2).all(|pair| pair[0] == pair[1]));
+
+    let (seam, _) = energy_matrix
+        .transpose()
+        .extract_horizontal_seam_with_constraint(
+            &mut rng,
+            false,
+            &SeamConstraint {
+                max_step: 3,
+                slope_penalty: 0.0,
+            },
+        )
+        .unwrap();
+    assert_eq!(seam.rows, [0, 3, 0, 3]);
+
+    let operation = next_seam_operation_with_constraint(
+        &energy_matrix,
+        &WindowSize {
+            width: 3,
+            height: 4,
+        },
+        &SeamConstraint {
+            max_step: 3,
+            slope_penalty: 0.0,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(
+        operation,
+        Some(SeamOperation::CarveVertical(VerticalSeam {
+            columns: vec![0, 3, 0, 3]
+        }))
+    );
+}
+
+#[test]
+fn seams_with_longer_steps_are_carved_and_inserted() {
+    let constraint = SeamConstraint {
+        max_step: 3,
+        slope_penalty: 0.0,
+    };
+    let energy_matrix: Matrix<GradientMagnitudePoint> = Matrix::new(
+        Vec::from(
+            [
+                0.0, 5.0, 5.0, 5.0, //
+                5.0, 5.0, 5.0, 0.0, //
+                0.0, 5.0, 5.0, 5.0,
+            ]
+            .map(|value| GradientMagnitudePoint {
+                value,
+                is_inserted: false,
+            }),
+        ),
+        4,
+    );
+    let (seam, _) = energy_matrix
+        .extract_vertical_seam_with_constraint(&mut thread_rng(), false, &constraint)
+        .unwrap();
+    assert_eq!(seam.columns, [0, 3, 0]);
+    let image = Matrix::new((0..12).map(|value| value as f32).collect(), 4);
+
+    let mut carved = image.clone();
+    carved
+        .carve_vertical_seam_with_constraint(&seam, &constraint)
+        .unwrap();
+    let mut inserted = image.clone();
+    SeamOperation::InsertVertical(seam.clone())
+        .apply_with_constraint(&mut inserted, Interpolation::Duplicate, &constraint)
+        .unwrap();
+
+    assert_eq!(
+        carved.vector,
+        [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 9.0, 10.0, 11.0]
+    );
+    assert_eq!(
+        inserted.vector,
+        [0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 7.0, 8.0, 8.0, 9.0, 10.0, 11.0]
+    );
+    assert!(image.clone().insert_vertical_seam(&seam).is_err());
+
+    let mut sequence = SeamSequence::new(4, 3);
+    sequence.constraint = constraint;
+    sequence.operations = vec![
+        SeamOperation::InsertVertical(seam.clone()),
+        SeamOperation::CarveVertical(seam),
+    ];
+    let mut replayed = image.clone();
+    sequence.replay(&mut replayed).unwrap();
+    assert_eq!((replayed.width(), replayed.height()), (4, 3));
+}
+
+#[test]
+fn horizontal_search_matches_the_transposed_vertical_search() {
+    let mut rng = thread_rng();
+    let costs = Matrix::new((0..7 * 5).map(|_| rng.gen::<f32>()).collect(), 7);
+    for max_step in 0..4 {
+        let constraint = SeamConstraint {
+            max_step,
+            slope_penalty: 0.1,
+        };
+
+        let seam = costs
+            .min_horizontal_seam_with_constraint(&mut rng, &constraint)
+            .unwrap();
+        let transposed = costs
+            .transpose()
+            .min_vertical_seam_with_constraint(&mut rng, &constraint)
+            .unwrap();
+
+        assert_eq!(seam.rows, transposed.columns);
+    }
+}
+
 #[test]
 fn vertical_seam_insertion() {
     let first_color = CustomColor {
@@ -262,7 +443,7 @@ fn vertical_seam_insertion() {
     let seam = VerticalSeam {
         columns: Vec::from([0, 0, 0]),
     };
-    matrix.insert_vertical_seam(&seam);
+    matrix.insert_vertical_seam(&seam).unwrap();
 
     assert_eq!(
         matrix.vector,
@@ -321,7 +502,7 @@ fn horizontal_seam_insertion() {
     let seam = HorizontalSeam {
         rows: Vec::from([1, 0, 1]),
     };
-    matrix.insert_horizontal_seam(&seam);
+    matrix.insert_horizontal_seam(&seam).unwrap();
 
     assert_eq!(
         matrix.vector,
@@ -346,3 +527,116 @@ fn horizontal_seam_insertion() {
         ])
     );
 }
+
+#[test]
+fn degenerate_inputs_are_rejected() {
+    let mut rng = thread_rng();
+    let empty = Matrix::<GradientMagnitudePoint>::new(Vec::new(), 0);
+    assert!(matches!(
+        empty.extract_vertical_seam(&mut rng, false),
+        Err(Error::DegenerateImage { .. })
+    ));
+
+    let mut column = Matrix::new(vec![1.0_f32, 2.0, 3.0], 1);
+    assert!(matches!(
+        column.carve_vertical_seam(&VerticalSeam {
+            columns: vec![0, 0, 0]
+        }),
+        Err(Error::DegenerateImage {
+            width: 1,
+            height: 3
+        })
+    ));
+
+    let mut matrix = Matrix::new(vec![1.0_f32, 2.0, 3.0, 4.0], 2);
+    assert!(matches!(
+        matrix.carve_vertical_seam(&VerticalSeam { columns: vec![0] }),
+        Err(Error::SeamLength {
+            expected: 2,
+            found: 1
+        })
+    ));
+    assert!(matches!(
+        matrix.insert_horizontal_seam(&HorizontalSeam {
+            rows: vec![0, 0, 0]
+        }),
+        Err(Error::SeamLength {
+            expected: 2,
+            found: 3
+        })
+    ));
+    assert_eq!(matrix.vector, [1.0, 2.0, 3.0, 4.0]);
+
+    let energy = Matrix::new(
+        vec![
+            GradientMagnitudePoint {
+                value: 0.0,
+                is_inserted: false
+            };
+            4
+        ],
+        2,
+    );
+    assert!(next_seam_operation(
+        &energy,
+        &WindowSize {
+            height: 2,
+            width: 0
+        },
+        &mut rng
+    )
+    .is_err());
+}
+
+#[test]
+fn invalid_seams_are_rejected() {
+    let mut matrix = Matrix::new((0..12).map(|value| value as f32).collect(), 4);
+    let valid = VerticalSeam {
+        columns: vec![3, 2, 3],
+    };
+    assert!(valid.validate(&matrix).is_ok());
+    assert!(HorizontalSeam {
+        rows: vec![0, 1, 2, 2]
+    }
+    .validate(&matrix)
+    .is_ok());
+
+    let outside = VerticalSeam {
+        columns: vec![3, 4, 3],
+    };
+    assert!(matches!(
+        outside.validate(&matrix),
+        Err(Error::SeamOutOfBounds {
+            index: 1,
+            position: 4,
+            limit: 4
+        })
+    ));
+    let jumping = HorizontalSeam {
+        rows: vec![0, 2, 2, 1],
+    };
+    assert!(matches!(
+        jumping.validate(&matrix),
+        Err(Error::DisconnectedSeam { index: 1, step: 2 })
+    ));
+    assert!(jumping
+        .validate_with_constraint(
+            &matrix,
+            &SeamConstraint {
+                max_step: 2,
+                ..SeamConstraint::default()
+            }
+        )
+        .is_ok());
+
+    assert!(matrix.carve_vertical_seam(&outside).is_err());
+    assert!(matrix.carve_horizontal_seam(&jumping).is_err());
+    assert!(matrix
+        .insert_vertical_seam_with(&outside, |neighbours, index| neighbours[index])
+        .is_err());
+    assert_eq!(matrix.width(), 4);
+    assert_eq!(
+        matrix.vector,
+        (0..12).map(|value| value as f32).collect::<Vec<f32>>()
+    );
+}
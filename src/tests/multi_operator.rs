@@ -0,0 +1,99 @@
+use crate::{
+    energy::Energy,
+    multi_operator::{retarget, retarget_width, scale_matrix},
+    structs::{
+        color::CustomColor, interpolation::Interpolation, matrix::Matrix, window_size::WindowSize,
+    },
+};
+
+fn gray(value: f32) -> CustomColor {
+    CustomColor {
+        r: value,
+        g: value,
+        b: value,
+        is_inserted: false,
+    }
+}
+
+/// Checkerboard on the left, flat background on the right.
+fn half_textured_matrix(width: usize, height: usize) -> Matrix<CustomColor> {
+    Matrix::new(
+        (0..width * height)
+            .map(|index| {
+                let (row, column) = (index / width, index % width);
+                if column < width / 2 {
+                    gray(((row + column) % 2) as f32)
+                } else {
+                    gray(0.5)
+                }
+            })
+            .collect(),
+        width,
+    )
+}
+
+#[test]
+fn small_reductions_are_carved() {
+    let matrix = half_textured_matrix(16, 6);
+
+    let (result, mix) = retarget_width(&matrix, &Energy::default(), 14, 4).unwrap();
+
+    assert_eq!(result.width(), 14);
+    assert_eq!(result.height(), 6);
+    assert_eq!(mix.carved, 2);
+    assert_eq!(mix.cropped + mix.scaled, 0);
+}
+
+#[test]
+fn operator_amounts_add_up_to_the_reduction() {
+    let matrix = half_textured_matrix(20, 8);
+
+    let (result, mix) = retarget_width(&matrix, &Energy::default(), 8, 4).unwrap();
+
+    assert_eq!(result.width(), 8);
+    assert_eq!(mix.cropped + mix.carved + mix.scaled, 12);
+}
+
+#[test]
+fn both_dimensions_are_retargeted() {
+    let matrix = half_textured_matrix(12, 10);
+
+    let (result, _, _) = retarget(
+        &matrix,
+        &Energy::default(),
+        Interpolation::default(),
+        &WindowSize {
+            width: 7,
+            height: 6,
+        },
+        2,
+    )
+    .unwrap();
+
+    assert_eq!((result.width(), result.height()), (7, 6));
+}
+
+#[test]
+fn scaling_keeps_precision_and_inserted_pixels() {
+    let matrix = Matrix::new(
+        (0..8 * 3)
+            .map(|index| CustomColor {
+                is_inserted: index % 8 < 4,
+                ..gray(0.123)
+            })
+            .collect(),
+        8,
+    );
+
+    let result = scale_matrix(&matrix, 4, 2);
+
+    assert_eq!((result.width(), result.height()), (4, 2));
+    assert!(result
+        .vector
+        .iter()
+        .all(|pixel| (pixel.r - 0.123).abs() < 1e-5));
+    assert!(result.vector.chunks_exact(4).all(|row| row
+        .iter()
+        .map(|pixel| pixel.is_inserted)
+        .eq([true, true, false, false])));
+}
@@ -0,0 +1,91 @@
+use crate::{
+    energy::Energy,
+    poisson::{carve_gradient_domain, GradientDomainImage, PoissonSolver},
+    structs::{
+        color::CustomColor,
+        matrix::{Matrix, VerticalSeam},
+        window_size::WindowSize,
+    },
+};
+
+fn gray(value: f32) -> CustomColor {
+    CustomColor {
+        r: value,
+        g: value,
+        b: value,
+        is_inserted: false,
+    }
+}
+
+/// Brightness growing from left to right.
+fn ramp(width: usize, height: usize) -> Matrix<CustomColor> {
+    Matrix::new(
+        (0..width * height)
+            .map(|index| gray((index % width) as f32 / (width - 1) as f32))
+            .collect(),
+        width,
+    )
+}
+
+fn largest_step(matrix: &Matrix<CustomColor>) -> f32 {
+    matrix
+        .vector
+        .chunks_exact(matrix.width())
+        .flat_map(|row| row.windows(2).map(|pair| (pair[1].r - pair[0].r).abs()))
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn uncarved_images_are_reconstructed() {
+    let image = Matrix::new(
+        (0..30)
+            .map(|index| gray((index * 7 % 11) as f32 / 10.0))
+            .collect(),
+        6,
+    );
+
+    let result = GradientDomainImage::new(&image).reconstruct(&PoissonSolver::default());
+
+    assert!(result
+        .vector
+        .iter()
+        .zip(image.vector.iter())
+        .all(|(first, second)| (first.r - second.r).abs() < 1e-4));
+}
+
+#[test]
+fn seams_leave_no_step_behind() {
+    let image = ramp(16, 4);
+    let seam = VerticalSeam {
+        columns: vec![7; 4],
+    };
+
+    let mut direct = image.clone();
+    direct.carve_vertical_seam(&seam).unwrap();
+    let mut gradient_domain = GradientDomainImage::new(&image);
+    gradient_domain.carve_vertical_seam(&seam).unwrap();
+    let result = gradient_domain.reconstruct(&PoissonSolver {
+        iterations: 500,
+        data_weight: 0.01,
+    });
+
+    assert_eq!(result.width(), 15);
+    assert!(largest_step(&direct) > 0.13);
+    assert!(largest_step(&result) < 0.1);
+}
+
+#[test]
+fn images_are_carved_to_the_target() {
+    let result = carve_gradient_domain(
+        &ramp(10, 8),
+        &Energy::default(),
+        &WindowSize {
+            width: 7,
+            height: 12,
+        },
+        &PoissonSolver::default(),
+    )
+    .unwrap();
+
+    assert_eq!((result.width(), result.height()), (7, 8));
+}
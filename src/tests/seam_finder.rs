@@ -0,0 +1,114 @@
+use ::rand::{thread_rng, Rng};
+
+use crate::{
+    seam_finder::{compare_with_exact, Pyramid, SeamFinder},
+    structs::matrix::Matrix,
+    utils::GradientMagnitudePoint,
+};
+
+fn energy_matrix(
+    width: usize,
+    height: usize,
+    mut value: impl FnMut(usize, usize) -> f32,
+) -> Matrix<GradientMagnitudePoint> {
+    Matrix::new(
+        (0..width * height)
+            .map(|index| GradientMagnitudePoint {
+                value: value(index / width, index % width),
+                is_inserted: false,
+            })
+            .collect(),
+        width,
+    )
+}
+
+#[test]
+fn pyramid_seams_are_connected() {
+    let mut rng = thread_rng();
+    let energy = energy_matrix(37, 29, |_, _| rng.gen_range(0.0..1.0));
+    let mut rng = thread_rng();
+
+    let (vertical, _) = Pyramid::default()
+        .find_vertical_seam(&energy, &mut rng, false)
+        .unwrap();
+    let (horizontal, _) = Pyramid::default()
+        .find_horizontal_seam(&energy, &mut rng, false)
+        .unwrap();
+
+    assert_eq!(vertical.columns.len(), 29);
+    assert!(vertical
+        .columns
+        .windows(2)
+        .all(|pair| pair[0].abs_diff(pair[1]) <= 1 && pair[1] < 37));
+    assert_eq!(horizontal.rows.len(), 37);
+    assert!(horizontal
+        .rows
+        .windows(2)
+        .all(|pair| pair[0].abs_diff(pair[1]) <= 1 && pair[1] < 29));
+}
+
+#[test]
+fn pyramid_finds_a_wide_cheap_path_exactly() {
+    // a cheap diagonal valley a few pixels wide in otherwise costly energy
+    let energy = energy_matrix(64, 64, |row, column| {
+        if column.abs_diff(row / 2 + 10) <= 3 {
+            0.0
+        } else {
+            1.0
+        }
+    });
+    let mut rng = thread_rng();
+
+    let comparison = compare_with_exact(&Pyramid::default(), &energy, &mut rng).unwrap();
+
+    assert_eq!(comparison.exact_energy, 0.0);
+    assert_eq!(comparison.energy, 0.0);
+    assert_eq!(comparison.relative_error(), 0.0);
+}
+
+#[test]
+fn pyramid_stays_close_to_the_exact_seam() {
+    let mut rng = thread_rng();
+    // smooth random energy, which downscaling keeps the shape of
+    let phases = [(); 4].map(|_| rng.gen_range(0.0..std::f32::consts::TAU));
+    let energy = energy_matrix(200, 150, |row, column| {
+        1.0 + ((column as f32 / 13.0 + phases[0]).sin() * (row as f32 / 17.0 + phases[1]).cos()
+            + (column as f32 / 7.0 + phases[2]).cos() * (row as f32 / 29.0 + phases[3]).sin())
+            / 2.0
+    });
+
+    let comparison = compare_with_exact(&Pyramid::default(), &energy, &mut rng).unwrap();
+
+    assert!(comparison.energy >= comparison.exact_energy - 1e-3);
+    assert!(comparison.relative_error() < 0.1, "{:?}", comparison);
+}
+
+#[test]
+fn pyramid_seams_blocked_by_inserted_pixels_fall_back_to_the_exact_search() {
+    // every path through the bands around the coarse seam hits an inserted
+    // pixel
+    let mask = [
+        0, 0, 0, 1, 0, 1, //
+        0, 0, 1, 1, 1, 1, //
+        0, 0, 0, 0, 0, 0, //
+        1, 1, 0, 0, 1, 0, //
+        0, 1, 1, 1, 1, 1, //
+        0, 1, 1, 1, 1, 1, //
+        0, 0, 0, 1, 1, 0, //
+        1, 1, 1, 0, 0, 1, //
+        0, 0, 1, 0, 0, 1, //
+        1, 0, 0, 0, 1, 1,
+    ];
+    let mut energy = energy_matrix(6, 10, |_, _| 1.0);
+    for (point, inserted) in energy.vector.iter_mut().zip(mask) {
+        point.is_inserted = inserted == 1;
+    }
+    let mut rng = thread_rng();
+
+    for _ in 0..20 {
+        let (seam, _) = Pyramid { levels: 1, band: 0 }
+            .find_vertical_seam(&energy, &mut rng, true)
+            .unwrap();
+        assert!(seam.validate(&energy).is_ok());
+    }
+}
@@ -0,0 +1,172 @@
+use std::{env, fs};
+
+use crate::{
+    energy::Energy,
+    seam_carver::SeamOperation,
+    sequence::SeamSequence,
+    structs::{
+        color::CustomColor,
+        interpolation::Interpolation,
+        matrix::{HorizontalSeam, Matrix, SeamConstraint, VerticalSeam},
+        window_size::WindowSize,
+    },
+};
+
+fn image(width: usize, height: usize) -> Matrix<CustomColor> {
+    Matrix::new(
+        (0..width * height)
+            .map(|index| CustomColor {
+                r: (index * 7 % 13) as f32 / 12.0,
+                g: (index * 3 % 5) as f32 / 4.0,
+                b: 0.5,
+                is_inserted: false,
+            })
+            .collect(),
+        width,
+    )
+}
+
+#[test]
+fn operations_serialize_with_orientation_and_kind() {
+    let sequence = SeamSequence {
+        width: 3,
+        height: 2,
+        interpolation: Interpolation::Duplicate,
+        constraint: SeamConstraint {
+            max_step: 2,
+            slope_penalty: 0.5,
+        },
+        operations: vec![
+            SeamOperation::CarveVertical(VerticalSeam {
+                columns: vec![0, 1],
+            }),
+            SeamOperation::InsertHorizontal(HorizontalSeam { rows: vec![1, 0] }),
+        ],
+    };
+
+    let json = serde_json::to_string(&sequence).unwrap();
+
+    assert_eq!(
+        json,
+        r#"{"width":3,"height":2,"interpolation":"Duplicate","constraint":{"max_step":2,"slope_penalty":0.5},"operations":[{"CarveVertical":{"columns":[0,1]}},{"InsertHorizontal":{"rows":[1,0]}}]}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<SeamSequence>(&json).unwrap(),
+        sequence
+    );
+}
+
+#[test]
+fn stored_sequences_replay_on_another_rendition() {
+    let mut original = image(8, 6);
+    let sequence = SeamSequence::record(
+        &mut original,
+        &Energy::default(),
+        Interpolation::Duplicate,
+        &WindowSize {
+            width: 6,
+            height: 7,
+        },
+    )
+    .unwrap();
+    let path = env::temp_dir().join(format!("seam_carver_sequence_{}.json", std::process::id()));
+    sequence.save(&path).unwrap();
+    let loaded = SeamSequence::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // the same image as a single channel rendition
+    let mut rendition = Matrix::new(
+        image(8, 6)
+            .vector
+            .iter()
+            .map(|color| color.r)
+            .collect::<Vec<f32>>(),
+        8,
+    );
+    loaded.replay(&mut rendition).unwrap();
+
+    assert_eq!(loaded, sequence);
+    assert_eq!((rendition.width(), rendition.height()), (6, 7));
+    assert_eq!(
+        rendition.vector,
+        original
+            .vector
+            .iter()
+            .map(|color| color.r)
+            .collect::<Vec<f32>>()
+    );
+    assert!(loaded.replay(&mut Matrix::new(vec![0.0; 4], 2)).is_err());
+}
+
+fn is_connected(path: &[usize], across: usize) -> bool {
+    path.iter().all(|position| *position < across)
+        && path.windows(2).all(|pair| pair[0].abs_diff(pair[1]) <= 1)
+}
+
+#[test]
+fn upscaled_seams_stay_connected() {
+    let seam = VerticalSeam {
+        columns: vec![0, 1, 2, 2, 1, 0],
+    };
+    let proxy = WindowSize {
+        width: 4,
+        height: 6,
+    };
+    let master = WindowSize {
+        width: 13,
+        height: 17,
+    };
+
+    let upscaled = seam.upscale(&proxy, &master);
+    let transposed = HorizontalSeam {
+        rows: seam.columns.clone(),
+    }
+    .upscale(
+        &WindowSize {
+            width: 6,
+            height: 4,
+        },
+        &WindowSize {
+            width: 17,
+            height: 13,
+        },
+    );
+
+    assert_eq!(upscaled.columns.len(), 17);
+    assert!(is_connected(&upscaled.columns, 13));
+    assert_eq!(transposed.rows, upscaled.columns);
+}
+
+#[test]
+fn proxy_sequences_resize_the_master_in_proportion() {
+    let mut proxy = image(8, 6);
+    let sequence = SeamSequence::record(
+        &mut proxy,
+        &Energy::default(),
+        Interpolation::default(),
+        &WindowSize {
+            width: 5,
+            height: 8,
+        },
+    )
+    .unwrap();
+
+    let upscaled = sequence.upscale(24, 18);
+    let mut master = image(24, 18);
+    for operation in &upscaled.operations {
+        match operation {
+            SeamOperation::CarveVertical(seam) | SeamOperation::InsertVertical(seam) => {
+                assert_eq!(seam.columns.len(), master.height());
+                assert!(is_connected(&seam.columns, master.width()));
+            }
+            SeamOperation::CarveHorizontal(seam) | SeamOperation::InsertHorizontal(seam) => {
+                assert_eq!(seam.rows.len(), master.width());
+                assert!(is_connected(&seam.rows, master.height()));
+            }
+        }
+        operation.apply(&mut master).unwrap();
+    }
+
+    assert_eq!((master.width(), master.height()), (15, 24));
+    assert_eq!(upscaled.operations.len(), 9 + 6);
+}
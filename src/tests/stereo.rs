@@ -0,0 +1,131 @@
+use ::rand::{thread_rng, Rng};
+
+use crate::{
+    energy::Energy,
+    error::Error,
+    stereo::StereoPair,
+    structs::{
+        color::CustomColor,
+        matrix::{Matrix, VerticalSeam},
+    },
+};
+
+fn gray(value: f32) -> CustomColor {
+    CustomColor {
+        r: value,
+        g: value,
+        b: value,
+        is_inserted: false,
+    }
+}
+
+#[test]
+fn seams_keep_the_views_consistent() {
+    // textured views with a flat stripe, the right one shifted by 2 pixels
+    let (width, height, disparity) = (16, 6, 2);
+    let mut rng = thread_rng();
+    let left = Matrix::new(
+        (0..width * height)
+            .map(|index| {
+                if (6..10).contains(&(index % width)) {
+                    gray(0.5)
+                } else {
+                    gray(rng.gen_range(0.0..1.0))
+                }
+            })
+            .collect(),
+        width,
+    );
+    let right = Matrix::new(
+        (0..width * height)
+            .map(|index| {
+                if index % width + disparity < width {
+                    left.vector[index + disparity]
+                } else {
+                    gray(rng.gen_range(0.0..1.0))
+                }
+            })
+            .collect(),
+        width,
+    );
+    let mut pair = StereoPair::new(
+        left,
+        right,
+        Matrix::new(vec![disparity as f32; width * height], width),
+    )
+    .unwrap();
+
+    pair.retarget_width(&Energy::default(), 14, 1.0).unwrap();
+
+    assert_eq!(pair.left.width(), 14);
+    assert_eq!(pair.right.width(), 14);
+    assert!(pair.disparity.vector.iter().all(|value| *value == 2.0));
+    for row in 0..height {
+        for column in disparity..14 {
+            assert_eq!(
+                pair.left.vector[row * 14 + column].r,
+                pair.right.vector[row * 14 + column - disparity].r
+            );
+        }
+    }
+}
+
+#[test]
+fn disparities_follow_the_carved_pixels() {
+    let mut pair = StereoPair::new(
+        Matrix::new(vec![gray(0.0); 6], 6),
+        Matrix::new(vec![gray(0.0); 6], 6),
+        Matrix::new(vec![0.0, 0.0, 0.0, 0.0, 3.0, 0.0], 6),
+    )
+    .unwrap();
+    let left_seam = VerticalSeam { columns: vec![4] };
+
+    let right_seam = pair.corresponding_seam(&left_seam);
+    assert_eq!(right_seam.columns, [1]);
+    pair.carve_vertical_seams(&left_seam, &right_seam).unwrap();
+
+    assert_eq!(pair.disparity.vector, [0.0, 0.0, 1.0, 1.0, 0.0]);
+    assert_eq!(
+        pair.corresponding_seam(&VerticalSeam { columns: vec![0] })
+            .columns,
+        [0]
+    );
+}
+
+#[test]
+fn views_of_different_sizes_are_rejected() {
+    let pair = StereoPair::new(
+        Matrix::new(vec![gray(0.0); 6], 3),
+        Matrix::new(vec![gray(0.0); 6], 2),
+        Matrix::new(vec![0.0; 6], 3),
+    );
+
+    assert!(matches!(
+        pair,
+        Err(Error::SizeMismatch {
+            expected: (3, 2),
+            found: (2, 3)
+        })
+    ));
+}
+
+#[test]
+fn failed_carves_leave_the_pair_unchanged() {
+    let mut pair = StereoPair::new(
+        Matrix::new(vec![gray(0.2), gray(0.4)], 1),
+        Matrix::new(vec![gray(0.6), gray(0.8)], 1),
+        Matrix::new(vec![1.0, -1.0], 1),
+    )
+    .unwrap();
+    let seam = VerticalSeam {
+        columns: vec![0, 0],
+    };
+
+    assert!(matches!(
+        pair.carve_vertical_seams(&seam, &seam),
+        Err(Error::DegenerateImage { .. })
+    ));
+    assert_eq!(pair.left.vector, [gray(0.2), gray(0.4)]);
+    assert_eq!(pair.right.vector, [gray(0.6), gray(0.8)]);
+    assert_eq!(pair.disparity.vector, [1.0, -1.0]);
+}
@@ -0,0 +1,113 @@
+use ::image::RgbaImage;
+use ::rand::{thread_rng, Rng};
+use std::{env, fs};
+
+use crate::{
+    error::Result,
+    streaming::{narrow_png_streamed, streamed_vertical_seam, RowSource},
+    structs::color::CustomColor,
+    utils::{
+        gradient_magnitude_with_border, matrix_to_rgba_image, rgba_image_to_matrix, BorderMode,
+    },
+};
+
+#[test]
+fn streamed_tiles_are_carved_like_the_whole_image() {
+    let directory = env::temp_dir().join(format!("seam_carver_streaming_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let mut rng = thread_rng();
+    let image = RgbaImage::from_fn(14, 9, |_, _| {
+        ::image::Rgba([rng.gen(), rng.gen(), rng.gen(), 255])
+    });
+    let tiles = [0, 6]
+        .iter()
+        .zip([6, 8])
+        .enumerate()
+        .map(|(index, (x, width))| {
+            let path = directory.join(format!("tile_{}.png", index));
+            ::image::imageops::crop_imm(&image, *x, 0, width, 9)
+                .to_image()
+                .save(&path)
+                .unwrap();
+            path
+        })
+        .collect::<Vec<_>>();
+    let output = directory.join("narrowed.png");
+
+    let results = [BorderMode::Zero, BorderMode::Replicate, BorderMode::Reflect].map(|border| {
+        narrow_png_streamed(&tiles, &output, 10, border, &directory.join("work")).unwrap();
+
+        let mut matrix = rgba_image_to_matrix(&image);
+        while matrix.width() > 10 {
+            let (seam, _) = gradient_magnitude_with_border(&matrix, border)
+                .extract_vertical_seam(&mut rng, false)
+                .unwrap();
+            matrix.carve_vertical_seam(&seam).unwrap();
+        }
+        (
+            ::image::open(&output).unwrap().to_rgba8(),
+            matrix_to_rgba_image(&matrix),
+        )
+    });
+    fs::remove_dir_all(&directory).unwrap();
+
+    for (streamed, carved) in results {
+        assert_eq!(streamed.dimensions(), (10, 9));
+        assert_eq!(streamed, carved);
+    }
+}
+
+#[test]
+fn output_widths_out_of_range_are_clamped() {
+    let directory = env::temp_dir().join(format!(
+        "seam_carver_streaming_widths_{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&directory).unwrap();
+    let tile = directory.join("tile.png");
+    RgbaImage::from_fn(4, 3, |x, y| {
+        ::image::Rgba([(x * 60) as u8, (y * 80) as u8, 0, 255])
+    })
+    .save(&tile)
+    .unwrap();
+    let output = directory.join("narrowed.png");
+
+    let sizes = [0, 9].map(|target_width| {
+        narrow_png_streamed(
+            std::slice::from_ref(&tile),
+            &output,
+            target_width,
+            BorderMode::Zero,
+            &directory.join("work"),
+        )
+        .unwrap();
+        ::image::open(&output).unwrap().to_rgba8().dimensions()
+    });
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(sizes, [(1, 3), (4, 3)]);
+}
+
+struct EmptyRows;
+
+impl RowSource for EmptyRows {
+    fn width(&self) -> usize {
+        5
+    }
+    fn height(&self) -> usize {
+        0
+    }
+    fn next_row(&mut self) -> Result<Vec<CustomColor>> {
+        unreachable!("an image without rows has no row to read")
+    }
+}
+
+#[test]
+fn images_without_rows_have_no_seam() {
+    let path = env::temp_dir().join(format!(
+        "seam_carver_streaming_empty_{}.bin",
+        std::process::id()
+    ));
+
+    assert!(streamed_vertical_seam(&mut EmptyRows, BorderMode::Zero, &path).is_err());
+}
@@ -0,0 +1,132 @@
+use crate::{
+    energy::Energy,
+    structs::{color::CustomColor, matrix::Matrix},
+    symmetry::{carve_symmetric_width, extract_mirrored_vertical_seams, SymmetryAxis},
+    utils::{gradient_magnitude, BorderMode},
+};
+use ::rand::{thread_rng, Rng};
+
+fn gray(value: f32) -> CustomColor {
+    CustomColor {
+        r: value,
+        g: value,
+        b: value,
+        is_inserted: false,
+    }
+}
+
+/// Random image mirrored around the middle.
+fn symmetric_image(width: usize, height: usize) -> Matrix<CustomColor> {
+    let mut rng = thread_rng();
+    let mut vector = vec![gray(0.0); width * height];
+    for row in 0..height {
+        for column in 0..width.div_ceil(2) {
+            let color = gray(rng.gen_range(0.0..1.0));
+            vector[row * width + column] = color;
+            vector[row * width + width - 1 - column] = color;
+        }
+    }
+    Matrix::new(vector, width)
+}
+
+fn is_symmetric(matrix: &Matrix<CustomColor>, axis: &SymmetryAxis) -> bool {
+    let width = matrix.width();
+    (0..matrix.height()).all(|row| {
+        (0..width)
+            .filter_map(|column| Some((column, axis.mirror(column)?)))
+            .filter(|(_, mirrored)| *mirrored < width)
+            .all(|(column, mirrored)| {
+                matrix.vector[row * width + column].r == matrix.vector[row * width + mirrored].r
+            })
+    })
+}
+
+#[test]
+fn mirrored_seams_stay_on_their_side() {
+    let image = symmetric_image(9, 6);
+    let axis = SymmetryAxis::centered(9);
+
+    let (left, right, _) = extract_mirrored_vertical_seams(
+        &gradient_magnitude(&image),
+        &axis,
+        &mut thread_rng(),
+        false,
+    )
+    .unwrap()
+    .unwrap();
+
+    assert!(left.columns.iter().all(|column| *column < 4));
+    assert!(left
+        .columns
+        .iter()
+        .zip(right.columns.iter())
+        .all(|(left, right)| left + right == 8));
+}
+
+#[test]
+fn carving_keeps_the_image_symmetric() {
+    for (width, target_width) in [(12, 6), (11, 6), (12, 7)] {
+        let mut image = symmetric_image(width, 8);
+
+        let axis = carve_symmetric_width(
+            &mut image,
+            SymmetryAxis::centered(width),
+            target_width,
+            &Energy {
+                border: BorderMode::Zero,
+                ..Energy::default()
+            },
+            &mut thread_rng(),
+        )
+        .unwrap();
+
+        // an even image cannot lose an odd number of columns symmetrically
+        let expected_width = target_width + (width - target_width) % 2 * (1 - width % 2);
+        assert_eq!(image.width(), expected_width);
+        assert_eq!(axis, SymmetryAxis::centered(expected_width));
+        assert!(is_symmetric(&image, &axis));
+    }
+}
+
+#[test]
+fn off_centre_axes_only_use_mirrored_columns() {
+    let axis = SymmetryAxis::at(1.5);
+    let image = symmetric_image(8, 4);
+
+    let (left, right, _) = extract_mirrored_vertical_seams(
+        &gradient_magnitude(&image),
+        &axis,
+        &mut thread_rng(),
+        false,
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(axis.mirror(1), Some(2));
+    assert_eq!(axis.mirror(4), None);
+    assert!(left.columns.iter().all(|column| *column <= 1));
+    assert!(right.columns.iter().all(|column| (2..=3).contains(column)));
+    assert!(extract_mirrored_vertical_seams(
+        &gradient_magnitude(&image),
+        &SymmetryAxis::at(0.0),
+        &mut thread_rng(),
+        false
+    )
+    .unwrap()
+    .is_none());
+}
+
+#[test]
+fn empty_matrices_have_no_mirrored_seams() {
+    let axis = SymmetryAxis::centered(0);
+
+    assert_eq!(axis.position(), 0.0);
+    assert!(extract_mirrored_vertical_seams(
+        &Matrix::new(Vec::new(), 0),
+        &axis,
+        &mut thread_rng(),
+        false,
+    )
+    .unwrap()
+    .is_none());
+}
@@ -0,0 +1,85 @@
+use crate::structs::{
+    target_size::{Dimension, Target, TargetSize},
+    window_size::WindowSize,
+};
+
+fn size(width: usize, height: usize) -> WindowSize {
+    WindowSize { height, width }
+}
+
+fn resolve(size_target: TargetSize, fit: bool) -> (usize, usize) {
+    let target = Target {
+        size: size_target,
+        fit,
+    };
+    let resolved = target.resolve(&size(1600, 1200), &size(500, 1500));
+    (resolved.width, resolved.height)
+}
+
+#[test]
+fn sides_resolve_against_the_source_image() {
+    let sized = |width, height| TargetSize::Size { width, height };
+
+    assert_eq!(resolve(TargetSize::Window, false), (500, 1500));
+    assert_eq!(
+        resolve(sized(Dimension::Pixels(1200), Dimension::Keep), false),
+        (1200, 1200)
+    );
+    assert_eq!(
+        resolve(
+            sized(Dimension::Pixels(1200), Dimension::Proportional),
+            false
+        ),
+        (1200, 900)
+    );
+    assert_eq!(
+        resolve(
+            sized(Dimension::Proportional, Dimension::Pixels(600)),
+            false
+        ),
+        (800, 600)
+    );
+    assert_eq!(
+        resolve(
+            sized(Dimension::Percent(80.0), Dimension::Percent(100.0)),
+            false
+        ),
+        (1280, 1200)
+    );
+}
+
+#[test]
+fn aspect_and_fit_only_carve_what_is_too_large() {
+    assert_eq!(
+        resolve(
+            TargetSize::Aspect {
+                width: 16,
+                height: 9
+            },
+            false
+        ),
+        (1600, 900)
+    );
+    assert_eq!(
+        resolve(
+            TargetSize::Aspect {
+                width: 1,
+                height: 1
+            },
+            false
+        ),
+        (1200, 1200)
+    );
+
+    assert_eq!(resolve(TargetSize::Window, true), (500, 1200));
+    assert_eq!(
+        resolve(
+            TargetSize::Size {
+                width: Dimension::Pixels(2000),
+                height: Dimension::Pixels(1000)
+            },
+            true
+        ),
+        (1600, 1000)
+    );
+}
@@ -0,0 +1,83 @@
+use ::rand::thread_rng;
+use std::{env, fs};
+
+use crate::{
+    energy::Energy,
+    structs::{color::CustomColor, matrix::Matrix},
+    utils::{load_matrix, save_matrix},
+    video::{extract_coherent_vertical_seams, retarget_frame_directory, retarget_frames},
+};
+
+fn frame(values: [f32; 16]) -> Matrix<CustomColor> {
+    Matrix::new(
+        Vec::from(values.map(|value| CustomColor {
+            r: value,
+            g: value,
+            b: value,
+            is_inserted: false,
+        })),
+        4,
+    )
+}
+
+#[test]
+fn temporal_weight_keeps_seams_in_place() {
+    // a flat frame has many equally cheap seams to pick from at random
+    let frames = vec![frame([0.0; 16]); 3];
+    let mut rng = thread_rng();
+
+    let seams =
+        extract_coherent_vertical_seams(&frames, &Energy::default(), 1.0, &mut rng).unwrap();
+
+    assert_eq!(seams.len(), 3);
+    for seam in seams.iter().skip(1) {
+        assert_eq!(seam.columns, seams[0].columns);
+    }
+}
+
+#[test]
+fn frames_are_narrowed_together() {
+    let mut frames = vec![
+        frame([
+            0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0,
+        ]),
+        frame([
+            1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0,
+        ]),
+    ];
+
+    retarget_frames(&mut frames, &Energy::default(), 2, 0.5).unwrap();
+
+    assert!(frames
+        .iter()
+        .all(|frame| frame.width() == 2 && frame.height() == 4));
+}
+
+#[test]
+fn frame_directories_keep_names_and_order() {
+    let directory = env::temp_dir().join(format!("seam_carver_video_{}", std::process::id()));
+    let input = directory.join("input");
+    fs::create_dir_all(&input).unwrap();
+    for (index, value) in [0.0, 0.5, 1.0].iter().enumerate() {
+        save_matrix(
+            &frame([*value; 16]),
+            &input.join(format!("frame_{}.png", index)),
+        )
+        .unwrap();
+    }
+    fs::write(input.join("notes.txt"), "not a frame").unwrap();
+    let output = directory.join("output");
+
+    retarget_frame_directory(&input, &output, &Energy::default(), 3, 0.5).unwrap();
+    let mut names = fs::read_dir(&output)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<String>>();
+    names.sort();
+    let last = load_matrix(&output.join("frame_2.png")).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(names, ["frame_0.png", "frame_1.png", "frame_2.png"]);
+    assert_eq!((last.width(), last.height()), (3, 4));
+    assert_eq!(last.vector[0].r, 1.0);
+}
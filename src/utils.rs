@@ -1,11 +1,15 @@
 use crate::{
-    structs::{color::CustomColor, matrix::Matrix},
+    error::Result,
+    structs::{
+        color::CustomColor,
+        linear::{linear_to_srgb, srgb_to_linear, LinearColor},
+        matrix::Matrix,
+        pixel::Pixel,
+    },
     *,
 };
-
-fn grayscale(color: CustomColor) -> f32 {
-    0.299 * color.r + 0.587 * color.g + 0.114 * color.b
-}
+use ::image::{DynamicImage, ImageBuffer, Rgb, Rgba, RgbaImage};
+use std::path::Path;
 
 #[derive(Clone, Copy)]
 pub struct GradientMagnitudePoint {
@@ -13,53 +17,120 @@ pub struct GradientMagnitudePoint {
     pub is_inserted: bool,
 }
 
-pub fn gradient_magnitude(matrix: &Matrix<CustomColor>) -> Matrix<GradientMagnitudePoint> {
-    let mut result = Matrix::new(
-        vec![
-            GradientMagnitudePoint {
-                value: 0.0,
-                is_inserted: false,
-            };
-            matrix.vector.len()
-        ],
-        matrix.width(),
-    );
-    let width = matrix.width();
-    let height = matrix.height();
+/// How values outside the matrix are made up when computing energies.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BorderMode {
+    /// Out-of-bounds values are zero, so borders get artificially high energy.
+    #[default]
+    Zero,
+    /// The nearest border value is repeated.
+    Replicate,
+    /// Values are mirrored around the border value, without repeating it.
+    Reflect,
+    /// The matrix repeats periodically.
+    Wrap,
+}
+
+impl BorderMode {
+    /// Index of the value standing in for `index + offset` in a line of
+    /// `length` values, or `None` if it should be zero.
+    pub fn neighbour(&self, index: usize, offset: isize, length: usize) -> Option<usize> {
+        let position = index as isize + offset;
+        if (0..length as isize).contains(&position) {
+            return Some(position as usize);
+        }
+        let last = length as isize - 1;
+        match self {
+            BorderMode::Zero => None,
+            BorderMode::Replicate => Some(position.clamp(0, last) as usize),
+            BorderMode::Reflect => Some(
+                (if position < 0 {
+                    -position
+                } else {
+                    2 * last - position
+                })
+                .clamp(0, last) as usize,
+            ),
+            BorderMode::Wrap => Some(position.rem_euclid(length as isize) as usize),
+        }
+    }
+}
+
+/// Magnitude of the central differences in both directions for every value,
+/// measured with `distance`. Out-of-bounds neighbours are taken according to
+/// `border`, with `zero` used for `BorderMode::Zero`.
+pub fn central_differences<V: Copy>(
+    values: &[V],
+    width: usize,
+    border: BorderMode,
+    zero: V,
+    distance: impl Fn(V, V) -> f32,
+) -> Vec<f32> {
+    let height = values.len() / width;
+    let vertical = |i: usize, j: usize, offset: isize| {
+        border
+            .neighbour(i, offset, height)
+            .map_or(zero, |row| values[row * width + j])
+    };
+    let horizontal = |i: usize, j: usize, offset: isize| {
+        border
+            .neighbour(j, offset, width)
+            .map_or(zero, |column| values[i * width + column])
+    };
+
+    let mut result = vec![0.0; values.len()];
     result
-        .vector
         .chunks_exact_mut(width)
         .enumerate()
         .for_each(|(i, vector)| {
-            for (j, point) in vector.iter_mut().enumerate() {
-                point.value = (((if i > 0 {
-                    grayscale(matrix.vector[(i - 1) * width + j])
-                } else {
-                    0.0
-                }) - (if i < height - 1 {
-                    grayscale(matrix.vector[(i + 1) * width + j])
-                } else {
-                    0.0
-                }))
-                .powi(2)
-                    + ((if j > 0 {
-                        grayscale(matrix.vector[i * width + j - 1])
-                    } else {
-                        0.0
-                    }) - (if j < width - 1 {
-                        grayscale(matrix.vector[i * width + j + 1])
-                    } else {
-                        0.0
-                    }))
-                    .powi(2))
+            for (j, value) in vector.iter_mut().enumerate() {
+                *value = (distance(vertical(i, j, -1), vertical(i, j, 1)).powi(2)
+                    + distance(horizontal(i, j, -1), horizontal(i, j, 1)).powi(2))
                 .sqrt();
-                point.is_inserted = matrix.vector[i * width + j].is_inserted;
             }
         });
 
     result
 }
 
+/// Central difference of the luma in both directions for every pixel, with
+/// out-of-bounds neighbours treated as 0.0.
+pub fn gradient_values<T: Pixel>(matrix: &Matrix<T>) -> Vec<f32> {
+    gradient_values_with_border(matrix, BorderMode::Zero)
+}
+
+pub fn gradient_values_with_border<T: Pixel>(matrix: &Matrix<T>, border: BorderMode) -> Vec<f32> {
+    let luma = matrix
+        .vector
+        .iter()
+        .map(|pixel| pixel.luma())
+        .collect::<Vec<f32>>();
+    central_differences(&luma, matrix.width(), border, 0.0, |first, second| {
+        first - second
+    })
+}
+
+pub fn gradient_magnitude<T: Pixel>(matrix: &Matrix<T>) -> Matrix<GradientMagnitudePoint> {
+    gradient_magnitude_with_border(matrix, BorderMode::Zero)
+}
+
+pub fn gradient_magnitude_with_border<T: Pixel>(
+    matrix: &Matrix<T>,
+    border: BorderMode,
+) -> Matrix<GradientMagnitudePoint> {
+    Matrix::new(
+        gradient_values_with_border(matrix, border)
+            .into_iter()
+            .zip(matrix.vector.iter())
+            .map(|(value, pixel)| GradientMagnitudePoint {
+                value,
+                is_inserted: pixel.is_inserted(),
+            })
+            .collect(),
+        matrix.width(),
+    )
+}
+
 pub fn image_to_matrix(image: &Image) -> Matrix<CustomColor> {
     Matrix::new(
         {
@@ -101,3 +172,101 @@ pub fn matrix_to_image(matrix: &Matrix<CustomColor>) -> Image {
     );
     image
 }
+
+pub fn rgba_image_to_matrix(image: &RgbaImage) -> Matrix<CustomColor> {
+    Matrix::new(
+        image
+            .pixels()
+            .map(|pixel| CustomColor {
+                r: pixel[0] as f32 / 255.0,
+                g: pixel[1] as f32 / 255.0,
+                b: pixel[2] as f32 / 255.0,
+                is_inserted: false,
+            })
+            .collect(),
+        image.width() as usize,
+    )
+}
+
+pub fn load_matrix(path: &Path) -> Result<Matrix<CustomColor>> {
+    Ok(rgba_image_to_matrix(&::image::open(path)?.to_rgba8()))
+}
+
+pub fn matrix_to_rgba_image(matrix: &Matrix<CustomColor>) -> RgbaImage {
+    RgbaImage::from_fn(matrix.width() as u32, matrix.height() as u32, |x, y| {
+        let color = matrix.vector[y as usize * matrix.width() + x as usize];
+        Rgba([
+            (color.r.clamp(0.0, 1.0) * 255.0).round() as u8,
+            (color.g.clamp(0.0, 1.0) * 255.0).round() as u8,
+            (color.b.clamp(0.0, 1.0) * 255.0).round() as u8,
+            255,
+        ])
+    })
+}
+
+pub fn save_matrix(matrix: &Matrix<CustomColor>, path: &Path) -> Result<()> {
+    Ok(matrix_to_rgba_image(matrix).save(path)?)
+}
+
+/// Loads any image the `image` crate reads at full precision. 8 and 16-bit
+/// images are taken as sRGB encoded and linearized, floating point ones
+/// (OpenEXR, Radiance HDR) are already linear.
+pub fn load_linear_matrix(path: &Path) -> Result<Matrix<LinearColor>> {
+    let image = ::image::open(path)?;
+    let is_linear = matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
+    let image = image.into_rgb32f();
+    let decode = |value: f32| {
+        if is_linear {
+            value
+        } else {
+            srgb_to_linear(value)
+        }
+    };
+    Ok(Matrix::new(
+        image
+            .pixels()
+            .map(|pixel| LinearColor {
+                r: decode(pixel[0]),
+                g: decode(pixel[1]),
+                b: decode(pixel[2]),
+                is_inserted: false,
+            })
+            .collect(),
+        image.width() as usize,
+    ))
+}
+
+/// Loads the luma of an image as plain values, at 16-bit or floating point
+/// precision where the file has it, without any sRGB decoding. Suits depth
+/// maps and other data stored as images.
+pub fn load_luma_matrix(path: &Path) -> Result<Matrix<f32>> {
+    let image = ::image::open(path)?.to_luma32f();
+    let width = image.width() as usize;
+    Ok(Matrix::new(image.into_raw(), width))
+}
+
+/// Saves `matrix` as linear floating point for `.exr` files and as 16-bit
+/// sRGB otherwise, which suits PNG and TIFF.
+pub fn save_linear_matrix(matrix: &Matrix<LinearColor>, path: &Path) -> Result<()> {
+    let (width, height) = (matrix.width() as u32, matrix.height() as u32);
+    let is_exr = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+    if is_exr {
+        let image: ImageBuffer<Rgb<f32>, Vec<f32>> = ImageBuffer::from_fn(width, height, |x, y| {
+            let color = matrix.vector[y as usize * matrix.width() + x as usize];
+            Rgb([color.r, color.g, color.b])
+        });
+        Ok(DynamicImage::ImageRgb32F(image).save(path)?)
+    } else {
+        let encode = |value: f32| (linear_to_srgb(value).clamp(0.0, 1.0) * 65535.0).round() as u16;
+        let image: ImageBuffer<Rgb<u16>, Vec<u16>> = ImageBuffer::from_fn(width, height, |x, y| {
+            let color = matrix.vector[y as usize * matrix.width() + x as usize];
+            Rgb([encode(color.r), encode(color.g), encode(color.b)])
+        });
+        Ok(image.save(path)?)
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{
+    energy::Energy,
+    error::{Error, Result},
+    structs::{
+        color::CustomColor,
+        matrix::{Matrix, VerticalSeam},
+    },
+    utils::*,
+};
+use ::rand::{rngs::ThreadRng, thread_rng};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+#[path = "tests/video.rs"]
+mod video_tests;
+
+/// Vertical seam for every frame of a sequence on `energy`, where each seam
+/// is pulled towards the seam of the previous frame by `temporal_weight` per
+/// pixel of horizontal displacement. Fails if the frames differ in size.
+pub fn extract_coherent_vertical_seams(
+    frames: &[Matrix<CustomColor>],
+    energy: &Energy,
+    temporal_weight: f32,
+    rng: &mut ThreadRng,
+) -> Result<Vec<VerticalSeam>> {
+    if let Some(first) = frames.first() {
+        let size = |frame: &Matrix<CustomColor>| (frame.width(), frame.height());
+        if let Some(frame) = frames.iter().find(|frame| size(frame) != size(first)) {
+            return Err(Error::SizeMismatch {
+                expected: size(first),
+                found: size(frame),
+            });
+        }
+    }
+
+    let mut seams: Vec<VerticalSeam> = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let energy_matrix = energy.color_energy(frame);
+        let width = energy_matrix.width();
+        let mut costs = Matrix::new(
+            energy_matrix
+                .vector
+                .iter()
+                .map(|point| point.value)
+                .collect::<Vec<f32>>(),
+            width,
+        );
+        if let Some(previous_seam) = seams.last() {
+            costs
+                .vector
+                .chunks_exact_mut(width)
+                .zip(previous_seam.columns.iter())
+                .for_each(|(row, previous_column)| {
+                    for (column, cost) in row.iter_mut().enumerate() {
+                        *cost += temporal_weight * column.abs_diff(*previous_column) as f32;
+                    }
+                });
+        }
+        seams.push(costs.min_vertical_seam(rng)?);
+    }
+    Ok(seams)
+}
+
+/// Narrows every frame of the sequence to `target_width`, removing one
+/// temporally coherent seam from all frames at a time.
+pub fn retarget_frames(
+    frames: &mut [Matrix<CustomColor>],
+    energy: &Energy,
+    target_width: usize,
+    temporal_weight: f32,
+) -> Result<()> {
+    let mut rng = thread_rng();
+    while frames
+        .first()
+        .is_some_and(|frame| frame.width() > target_width.max(1))
+    {
+        let seams = extract_coherent_vertical_seams(frames, energy, temporal_weight, &mut rng)?;
+        for (frame, seam) in frames.iter_mut().zip(seams.iter()) {
+            frame.carve_vertical_seam(seam)?;
+        }
+    }
+    Ok(())
+}
+
+/// Image files of `directory` in file name order, which is the frame order.
+pub fn frame_paths(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && ::image::ImageFormat::from_path(path).is_ok())
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Retargets every frame in `input` and writes the results under the same
+/// file names to `output`, producing a sequence of the same length.
+pub fn retarget_frame_directory(
+    input: &Path,
+    output: &Path,
+    energy: &Energy,
+    target_width: usize,
+    temporal_weight: f32,
+) -> Result<()> {
+    let paths = frame_paths(input)?;
+    let mut frames = paths
+        .iter()
+        .map(|path| load_matrix(path))
+        .collect::<Result<Vec<Matrix<CustomColor>>>>()?;
+
+    retarget_frames(&mut frames, energy, target_width, temporal_weight)?;
+
+    fs::create_dir_all(output)?;
+    for (path, frame) in paths.iter().zip(frames.iter()) {
+        let name = path.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} has no file name", path.display()),
+            )
+        })?;
+        save_matrix(frame, &output.join(name))?;
+    }
+    Ok(())
+}